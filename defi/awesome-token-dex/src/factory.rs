@@ -0,0 +1,89 @@
+use scrypto::prelude::*;
+
+use crate::invariant::Invariant;
+
+/// Registry entry of a pool created by the factory
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PoolInfo {
+    pub pool: ComponentAddress,
+    pub token_a: ResourceAddress,
+    pub token_b: ResourceAddress,
+    pub lp_token: ResourceAddress,
+    pub fee: Decimal,
+    pub invariant: Invariant,
+}
+
+// Awesome DEX factory component, creates and registers one pool per token pair
+#[blueprint]
+mod factory_module {
+    use crate::awesome_module::AwesomeDex;
+    use crate::factory::PoolInfo;
+    use crate::invariant::Invariant;
+
+    struct AwesomeDexFactory {
+        /// index into pools, keyed by token pair in both orders
+        pool_index: HashMap<(ResourceAddress, ResourceAddress), usize>,
+        /// all pools in order of creation
+        pools: Vec<PoolInfo>,
+    }
+
+    impl AwesomeDexFactory {
+        pub fn instantiate_factory() -> ComponentAddress {
+            Self {
+                pool_index: HashMap::new(),
+                pools: Vec::new(),
+            }
+            .instantiate()
+            .globalize()
+        }
+
+        /// Creates a pool for the token pair of the buckets with the given fee and invariant
+        /// Returns the pool's component address and the initial LP tokens
+        pub fn create_pool(
+            &mut self,
+            bucket_a: Bucket,
+            bucket_b: Bucket,
+            fee: Decimal,
+            invariant: Invariant,
+        ) -> (ComponentAddress, Bucket) {
+            let token_a: ResourceAddress = bucket_a.resource_address();
+            let token_b: ResourceAddress = bucket_b.resource_address();
+            assert!(token_a != token_b, "A pool needs two different tokens");
+            assert!(
+                !self.pool_index.contains_key(&(token_a, token_b)),
+                "A pool for this token pair already exists"
+            );
+
+            let (pool, lp_tokens): (ComponentAddress, Bucket) =
+                AwesomeDex::instantiate_awesome(bucket_a, bucket_b, fee, invariant.clone());
+
+            info!("Created pool {:?} for {:?} / {:?}", pool, token_a, token_b);
+
+            let index: usize = self.pools.len();
+            self.pools.push(PoolInfo {
+                pool,
+                token_a,
+                token_b,
+                lp_token: lp_tokens.resource_address(),
+                fee,
+                invariant,
+            });
+            self.pool_index.insert((token_a, token_b), index);
+            self.pool_index.insert((token_b, token_a), index);
+
+            (pool, lp_tokens)
+        }
+
+        /// Returns the pool for the token pair, in any order
+        pub fn get_pool(&self, token_a: ResourceAddress, token_b: ResourceAddress) -> Option<PoolInfo> {
+            self.pool_index
+                .get(&(token_a, token_b))
+                .map(|index| self.pools[*index].clone())
+        }
+
+        /// Returns all registered pools in order of creation
+        pub fn list_pools(&self) -> Vec<PoolInfo> {
+            self.pools.clone()
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use scrypto::prelude::*;
+
+/// Maximum number of Newton iterations when solving the stableswap invariant
+const MAX_ITERATIONS: u32 = 255;
+
+/// Pricing curve of a liquidity pool, picked when the pool is created
+#[derive(ScryptoSbor, radix_engine_common::ManifestSbor, Clone, Debug, PartialEq, Eq)]
+pub enum Invariant {
+    /// Uniswap style x * y = k, suited for any pair of tokens
+    ConstantProduct,
+    /// Curve style stableswap for pegged pairs. The higher the amplification coefficient, the flatter
+    /// the curve around the peg and the closer it gets to constant sum.
+    StableSwap { amplification: Decimal },
+}
+
+impl Invariant {
+    pub fn validate(&self) {
+        if let Invariant::StableSwap { amplification } = self {
+            assert!(
+                *amplification >= dec!("1"),
+                "Amplification coefficient must be at least 1"
+            );
+        }
+    }
+
+    /// Amount of output tokens received for `input_amount` (net of fees) given the current reserves
+    pub fn output_amount(
+        &self,
+        input_reserve: Decimal,
+        output_reserve: Decimal,
+        input_amount: Decimal,
+    ) -> Decimal {
+        match self {
+            Invariant::ConstantProduct => {
+                output_reserve * input_amount / (input_reserve + input_amount)
+            }
+            Invariant::StableSwap { amplification } => {
+                let d = stableswap_d(*amplification, input_reserve, output_reserve);
+                let new_output_reserve =
+                    stableswap_y(*amplification, input_reserve + input_amount, d);
+                // round in favour of the pool
+                if new_output_reserve >= output_reserve {
+                    Decimal::zero()
+                } else {
+                    output_reserve - new_output_reserve
+                }
+            }
+        }
+    }
+}
+
+/// Smallest difference between two iterations at which the Newton method is considered converged
+fn tolerance() -> Decimal {
+    dec!("0.000000000001")
+}
+
+/// Solves the two token stableswap invariant
+/// `A * n^n * (x + y) + D = A * D * n^n + D^(n+1) / (n^n * x * y)` for D, with n = 2
+fn stableswap_d(amplification: Decimal, x: Decimal, y: Decimal) -> Decimal {
+    let sum = x + y;
+    if sum == Decimal::zero() {
+        return Decimal::zero();
+    }
+
+    let ann = amplification * dec!("4");
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^3 / (4 * x * y), computed step by step to stay within Decimal range
+        let d_p = d * d / (x * dec!("2")) * d / (y * dec!("2"));
+        let d_prev = d;
+        d = (ann * sum + d_p * dec!("2")) * d / ((ann - dec!("1")) * d + d_p * dec!("3"));
+        if (d - d_prev).abs() <= tolerance() {
+            return d;
+        }
+    }
+    panic!("Stableswap invariant did not converge")
+}
+
+/// Solves the stableswap invariant for the reserve of one token given the reserve x of the other one and D
+fn stableswap_y(amplification: Decimal, x: Decimal, d: Decimal) -> Decimal {
+    let ann = amplification * dec!("4");
+    let c = d * d / (x * dec!("2")) * d / (ann * dec!("2"));
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        y = (y * y + c) / (y * dec!("2") + b - d);
+        if (y - y_prev).abs() <= tolerance() {
+            return y;
+        }
+    }
+    panic!("Stableswap invariant did not converge")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_product_output() {
+        let output = Invariant::ConstantProduct.output_amount(dec!("1000"), dec!("50"), dec!("10"));
+        assert_eq!(output, dec!("50") * dec!("10") / dec!("1010"));
+    }
+
+    #[test]
+    fn test_stableswap_d_of_balanced_pool_is_sum() {
+        let d = stableswap_d(dec!("100"), dec!("1000"), dec!("1000"));
+        assert!((d - dec!("2000")).abs() <= tolerance());
+    }
+
+    #[test]
+    fn test_stableswap_has_less_slippage_than_constant_product() {
+        let stable = Invariant::StableSwap { amplification: dec!("100") };
+        let stable_output = stable.output_amount(dec!("10000"), dec!("10000"), dec!("1000"));
+        let product_output =
+            Invariant::ConstantProduct.output_amount(dec!("10000"), dec!("10000"), dec!("1000"));
+
+        assert!(stable_output > product_output);
+        assert!(stable_output < dec!("1000"));
+        assert!(stable_output > dec!("999"));
+    }
+
+    #[test]
+    fn test_stableswap_keeps_invariant() {
+        let amplification = dec!("50");
+        let d_before = stableswap_d(amplification, dec!("5000"), dec!("7000"));
+        let output = Invariant::StableSwap { amplification }.output_amount(
+            dec!("5000"),
+            dec!("7000"),
+            dec!("500"),
+        );
+        let d_after = stableswap_d(amplification, dec!("5500"), dec!("7000") - output);
+        assert!((d_after - d_before).abs() <= dec!("0.000001"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_amplification_below_one_is_invalid() {
+        Invariant::StableSwap { amplification: dec!("0.5") }.validate();
+    }
+}
@@ -1,8 +1,15 @@
 use scrypto::prelude::*;
 
+mod factory;
+mod invariant;
+
+pub use invariant::Invariant;
+
 // Awesome DEX component
 #[blueprint]
 mod awesome_module {
+    use crate::invariant::Invariant;
+
     struct AwesomeDex {
         vault_a: Vault,
         vault_b: Vault,
@@ -14,6 +21,8 @@ mod awesome_module {
         reward_tokens_vault: Vault,
         /// Amount of fee charged on swaps which goes for LP rewards
         fee: Decimal,
+        /// Pricing curve used for swaps
+        invariant: Invariant,
     }
 
     impl AwesomeDex {
@@ -21,6 +30,7 @@ mod awesome_module {
             bucket_a: Bucket,
             bucket_b: Bucket,
             fee: Decimal,
+            invariant: Invariant,
         ) -> (ComponentAddress, Bucket) {
             // Accept no empty buckets
             assert!(
@@ -31,6 +41,7 @@ mod awesome_module {
                 fee >= dec!("0") && fee <= dec!("1"),
                 "Invalid fee in thousandths"
             );
+            invariant.validate();
 
             // Create badge for minting LP tracking unit token
             let lp_tokens_minter_badge: Bucket = ResourceBuilder::new_fungible()
@@ -70,6 +81,7 @@ mod awesome_module {
                 lp_tokens_minter_badge: Vault::with_bucket(lp_tokens_minter_badge),
                 reward_tokens_vault: Vault::with_bucket(awesome_tokens),
                 fee: fee,
+                invariant: invariant,
             }   
             .instantiate()
             .globalize();
@@ -94,13 +106,13 @@ mod awesome_module {
                     )
                 };
         
-            // Use constant maker function to calculate output amount
-            // output_amount = (current_total_output_pool_amount * lp_fee * input_amount) / (current_total_input_pool_amount + lp_fee * input_amount)
-            let output_amount: Decimal = (output_tokens_vault.amount() 
-                * (dec!("1") - self.fee)
-                * input_tokens.amount())
-                / (input_tokens_vault.amount() + input_tokens.amount()
-                * (dec!("1") - self.fee));
+            // Use the pool's invariant to calculate output amount, the fee stays in the pool
+            // e.g. constant product: output_amount = (current_total_output_pool_amount * lp_fee * input_amount) / (current_total_input_pool_amount + lp_fee * input_amount)
+            let output_amount: Decimal = self.invariant.output_amount(
+                input_tokens_vault.amount(),
+                output_tokens_vault.amount(),
+                input_tokens.amount() * (dec!("1") - self.fee),
+            );
             
             info!("input_amount {}", input_tokens.amount());
             info!("output_amount {}", output_amount);
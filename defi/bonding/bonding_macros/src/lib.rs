@@ -1,12 +1,16 @@
 extern crate proc_macro;
 use proc_macro::{TokenStream};
 use proc_macro2::{Span};
-use quote::{quote};
-use syn::{parse_macro_input, ItemImpl, ImplItem, ImplItemMethod, ItemTrait, TraitItem, Ident, Block, Visibility, VisPublic, token::Pub};
-//use syn::{ReturnType};
+use quote::{quote, format_ident};
+use syn::{parse_macro_input, ItemImpl, ImplItem, ImplItemMethod, ItemTrait, TraitItem, TraitItemMethod, Ident, Block, Visibility, VisPublic, token::Pub};
+use syn::{FnArg, Pat, ReturnType};
 
 /// a macro to generate an empty blueprint just to get the stub functions for inter-blueprint calls
 /// avoids manually specifying an ABI, and allows a concise definition using trait syntax
+///
+/// also generates a typed client `<Trait>Client` wrapping a `ComponentAddress`, with one method per trait method
+/// taking `&self` or `&mut self` which encodes the arguments and calls the component.  The client can be stored in
+/// component state too
 #[proc_macro_attribute]
 pub fn blueprint_stub(_metadata: TokenStream, input: TokenStream) -> TokenStream {
     // parse the trait
@@ -21,6 +25,15 @@ pub fn blueprint_stub(_metadata: TokenStream, input: TokenStream) -> TokenStream
         }
     });
     let mut the_impl = parse_macro_input!(the_impl as ItemImpl);
+    // the client methods, generated from the trait methods before they get their stub bodies
+    let client_methods: Vec<proc_macro2::TokenStream> = input
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Method(method) => client_method(method),
+            _ => None,
+        })
+        .collect();
     // iterate over the methods creating the implementation and filling the impl items
     // let decimal_ident = Ident::new(&format!("Decimal"), Span::call_site()); // used to find Decimal type
     let items = &mut input.items;
@@ -93,6 +106,7 @@ pub fn blueprint_stub(_metadata: TokenStream, input: TokenStream) -> TokenStream
     // a blueprint! with the geenerated impl, but all inside a module so the code is not callable
     // then reexport only the stubs
     let mod_name = Ident::new(&format!("internal_{}", ident), Span::call_site());
+    let client_ident = format_ident!("{}Client", ident);
     TokenStream::from(quote!{
         mod #mod_name {
         use super::*;
@@ -103,5 +117,69 @@ pub fn blueprint_stub(_metadata: TokenStream, input: TokenStream) -> TokenStream
         }
         // reexport the stub only
         pub use #mod_name::#ident;
+
+        /// typed client for calling a component implementing the interface
+        #[allow(unused)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, TypeId, Encode, Decode, Describe)]
+        pub struct #client_ident {
+            component_address: ComponentAddress,
+        }
+
+        #[allow(unused)]
+        impl #client_ident {
+            pub fn new(component_address: ComponentAddress) -> Self {
+                Self { component_address }
+            }
+
+            pub fn component_address(&self) -> ComponentAddress {
+                self.component_address
+            }
+
+            #(#client_methods)*
+        }
+
+        impl From<ComponentAddress> for #client_ident {
+            fn from(component_address: ComponentAddress) -> Self {
+                Self::new(component_address)
+            }
+        }
+    })
+}
+
+/// the client method calling `method` on the wrapped component, or None for functions without a receiver
+fn client_method(method: &TraitItemMethod) -> Option<proc_macro2::TokenStream> {
+    let sig = &method.sig;
+    let mut inputs = sig.inputs.iter();
+    match inputs.next() {
+        Some(FnArg::Receiver(_)) => {}
+        _ => return None,
+    }
+
+    // name any argument that isn't a plain identifier so it can be passed on
+    let (arg_idents, arg_types): (Vec<Ident>, Vec<syn::Type>) = inputs
+        .enumerate()
+        .filter_map(|(i, arg)| match arg {
+            FnArg::Typed(pat_type) => {
+                let arg_ident = match *pat_type.pat {
+                    Pat::Ident(ref pat_ident) => pat_ident.ident.clone(),
+                    _ => format_ident!("arg{}", i),
+                };
+                Some((arg_ident, (*pat_type.ty).clone()))
+            }
+            _ => None,
+        })
+        .unzip();
+
+    let method_ident = &sig.ident;
+    let method_name = method_ident.to_string();
+    let output = match sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ref ty) => quote! { #ty },
+    };
+
+    Some(quote! {
+        pub fn #method_ident(&self, #(#arg_idents: #arg_types),*) -> #output {
+            borrow_component!(self.component_address).call::<#output>(#method_name, args![#(#arg_idents),*])
+        }
     })
 }
\ No newline at end of file
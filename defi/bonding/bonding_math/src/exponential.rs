@@ -0,0 +1,83 @@
+use crate::integral::IntegralCurve;
+use crate::number::*;
+use crate::Amount;
+
+/// price = initial_price * e^(growth_rate * supply)
+#[derive(Clone, Debug)]
+pub struct ExponentialCurve {
+    initial_price: Number,
+    growth_rate: Number,
+    precision_bits: u16,
+}
+
+impl ExponentialCurve {
+    pub fn new(initial_price: Amount, growth_rate: Amount, precision_bits: u16) -> Self {
+        assert!(initial_price > 0, "initial price must be positive");
+        assert!(growth_rate > 0, "growth rate must be positive");
+        Self {
+            initial_price: number_from_amount(initial_price, precision_bits),
+            growth_rate: number_from_amount(growth_rate, precision_bits),
+            precision_bits,
+        }
+    }
+}
+
+impl IntegralCurve for ExponentialCurve {
+    fn precision_bits(&self) -> u16 {
+        self.precision_bits
+    }
+
+    fn price_at(&self, s: &Number) -> Number {
+        let bits = self.precision_bits;
+        mul(&self.initial_price, &exp(&mul(&self.growth_rate, s, bits), bits), bits)
+    }
+
+    fn reserve_at(&self, s: &Number) -> Number {
+        // initial_price / growth_rate * (e^(growth_rate * s) - 1)
+        let bits = self.precision_bits;
+        let growth = exp(&mul(&self.growth_rate, s, bits), bits) - number_one(bits);
+        div(&mul(&self.initial_price, &growth, bits), &self.growth_rate, bits)
+    }
+
+    fn supply_at(&self, r: &Number) -> Number {
+        // ln(1 + r * growth_rate / initial_price) / growth_rate
+        let bits = self.precision_bits;
+        let x = number_one(bits) + div(&mul(r, &self.growth_rate, bits), &self.initial_price, bits);
+        div(&ln(&x, bits), &self.growth_rate, bits)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::integral::assert_round_trips_never_profit;
+    use crate::AMOUNT_ONE;
+
+    #[test]
+    fn test_price_grows_exponentially() {
+        // price doubles every ln(2) / 0.01 ~= 69.31 tokens
+        let curve = ExponentialCurve::new(AMOUNT_ONE / 2, AMOUNT_ONE / 100, 384);
+        assert_eq!(curve.get_price(0, 0), AMOUNT_ONE / 2);
+        assert_eq!(curve.get_price(0, 69_314718055994530942), AMOUNT_ONE);
+    }
+
+    #[test]
+    fn test_buy_from_zero() {
+        // reserve(s) = 0.5 / 0.01 * (e^(0.01 * s) - 1) so 50 collateral buys 100 * ln(2)
+        let curve = ExponentialCurve::new(AMOUNT_ONE / 2, AMOUNT_ONE / 100, 384);
+        let minted = curve.get_initial_supply(50 * AMOUNT_ONE);
+        assert_eq!(minted, 69_314718055994530941);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        assert_round_trips_never_profit(&ExponentialCurve::new(AMOUNT_ONE / 2, AMOUNT_ONE / 10000, 384));
+        assert_round_trips_never_profit(&ExponentialCurve::new(100 * AMOUNT_ONE, AMOUNT_ONE / 100000, 384));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_growth_rate_is_invalid() {
+        ExponentialCurve::new(AMOUNT_ONE, 0, 384);
+    }
+}
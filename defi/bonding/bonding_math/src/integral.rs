@@ -0,0 +1,158 @@
+use num_traits::{Signed, Zero};
+
+use crate::number::*;
+use crate::Amount;
+#[cfg(test)]
+use crate::AMOUNT_ONE;
+
+/// Math shared by curves defined by a price function of the supply, where the reserve backing a supply is the area
+/// under the price curve.  A buy moves the supply up the curve until the area grew by the collateral paid and a sell
+/// moves it back down, so amounts only depend on the supply and a buy followed by a sell ends where it started.
+///
+/// Amounts handed out are rounded down so that round trip can never return more than what was paid.
+pub trait IntegralCurve {
+    fn precision_bits(&self) -> u16;
+
+    /// price of the next continuous token at supply `s`
+    fn price_at(&self, s: &Number) -> Number;
+
+    /// reserve backing supply `s`, ie. the integral of the price from 0 to `s`
+    fn reserve_at(&self, s: &Number) -> Number;
+
+    /// supply backed by reserve `r`, the inverse of `reserve_at`
+    fn supply_at(&self, r: &Number) -> Number;
+
+    fn get_initial_supply(&self, collateral_amount: Amount) -> Amount {
+        assert!(collateral_amount >= 0);
+
+        if collateral_amount == 0 {
+            return 0;
+        }
+
+        let precision_bits = self.precision_bits();
+        let c = number_from_amount(collateral_amount, precision_bits);
+
+        let result = self.supply_at(&c);
+
+        assert!(result >= Number::zero(), "Calculated negative initial supply");
+
+        amount_from_number_floor(result, precision_bits).unwrap()
+    }
+
+    fn get_mint_amount(
+        &self,
+        collateral_amount: Amount,
+        reserve_amount: Amount,
+        supply_amount: Amount,
+    ) -> Amount {
+        assert!(collateral_amount >= 0);
+        assert!(reserve_amount >= 0);
+        assert!(supply_amount >= 0);
+
+        if collateral_amount == 0 {
+            return 0;
+        }
+
+        let precision_bits = self.precision_bits();
+        let c = number_from_amount(collateral_amount, precision_bits);
+        let s = number_from_amount(supply_amount, precision_bits);
+
+        let result = self.supply_at(&(self.reserve_at(&s) + c)) - &s;
+
+        // approximation error can land a hair below the current supply when buying dust
+        if result.is_negative() {
+            return 0;
+        }
+
+        amount_from_number_floor(result, precision_bits).unwrap()
+    }
+
+    fn get_return_amount(
+        &self,
+        continuous_amount: Amount,
+        reserve_amount: Amount,
+        supply_amount: Amount,
+    ) -> Amount {
+        assert!(continuous_amount >= 0);
+        assert!(reserve_amount >= 0);
+        assert!(supply_amount >= 0);
+        assert!(
+            continuous_amount <= supply_amount,
+            "Cannot sell more than the supply"
+        );
+
+        if continuous_amount == 0 {
+            return 0;
+        }
+
+        let precision_bits = self.precision_bits();
+        let c = number_from_amount(continuous_amount, precision_bits);
+        let r = number_from_amount(reserve_amount, precision_bits);
+        let s = number_from_amount(supply_amount, precision_bits);
+
+        let result = self.reserve_at(&s) - self.reserve_at(&(&s - c));
+
+        // never pay out more than the reserve actually holds
+        let result = if result > r { r } else { result };
+        if result.is_negative() {
+            return 0;
+        }
+
+        amount_from_number_floor(result, precision_bits).unwrap()
+    }
+
+    fn get_price(&self, _reserve_amount: Amount, supply_amount: Amount) -> Amount {
+        assert!(supply_amount >= 0);
+
+        let precision_bits = self.precision_bits();
+        let s = number_from_amount(supply_amount, precision_bits);
+
+        amount_from_number(self.price_at(&s), precision_bits).unwrap()
+    }
+}
+
+/// Buys with `collateral_amount` at `supply_amount` (backed by the reserve the curve expects) and sells everything
+/// minted right back, returning the collateral received from the sale
+pub fn buy_then_sell<C: IntegralCurve>(
+    curve: &C,
+    collateral_amount: Amount,
+    supply_amount: Amount,
+) -> Amount {
+    let precision_bits = curve.precision_bits();
+    let s = number_from_amount(supply_amount, precision_bits);
+    let reserve_amount = amount_from_number(curve.reserve_at(&s), precision_bits).unwrap();
+
+    let minted = curve.get_mint_amount(collateral_amount, reserve_amount, supply_amount);
+    curve.get_return_amount(
+        minted,
+        reserve_amount + collateral_amount,
+        supply_amount + minted,
+    )
+}
+
+/// Round trips over a spread of trade sizes and starting supplies never return more than was paid, and
+/// lose no more than rounding dust
+#[cfg(test)]
+pub fn assert_round_trips_never_profit<C: IntegralCurve>(curve: &C) {
+    let collaterals = [1, AMOUNT_ONE, 250_500000000000000000, 100000 * AMOUNT_ONE]; // 1e-18, 1, 250.5, 100000
+    let supplies = [0, AMOUNT_ONE, 1000 * AMOUNT_ONE, 123456_789000000000000000]; // 0, 1, 1000, 123456.789
+    for supply_amount in supplies {
+        for collateral_amount in collaterals {
+            let returned = buy_then_sell(curve, collateral_amount, supply_amount);
+            assert!(
+                returned <= collateral_amount,
+                "round trip of {} at supply {} returned {}",
+                collateral_amount,
+                supply_amount,
+                returned
+            );
+            assert!(
+                collateral_amount - returned <= 1_000000000000, // 0.000001
+                "round trip of {} at supply {} lost {}",
+                collateral_amount,
+                supply_amount,
+                collateral_amount - returned
+            );
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! bonding_math: the curve math behind the scrypto-bonding blueprints, without any dependency on Scrypto
+//!
+//! The blueprints are thin wrappers around this crate, so wallets and simulation tooling computing quotes off-ledger
+//! get exactly the same amounts as the components on-ledger.
+//!
+//! Amounts are `i128` fixed point numbers with 18 decimal places, the same representation as a Scrypto `Decimal`.
+//! Internally everything is computed with the arbitrary precision `Number`.  Bounded (BigInt) or unbounded (BigRational)
+//! precision is configurable with the `use_rationals` feature flag.  Run the tests with and without
+//! `--no-default-features` to check both.
+//!
+//! * `ratio`: Bancor-style curves parametrized by "reserve ratio"
+//! * `linear`, `exponential`, `sigmoid`: curves defined by their price function, see `integral::IntegralCurve`
+pub mod exponential; // price grows exponentially with the supply
+pub mod integral; // shared math for curves defined by a price function, the reserve is the area under it
+pub mod linear; // price grows linearly with the supply
+pub mod number; // arbitrary precision math
+pub mod ratio; // price set by the reserve ratio, the default curve
+pub mod sigmoid; // S-curve price which levels off at a cap
+
+/// fixed point amount with 18 decimal places, same as a Scrypto `Decimal`
+pub type Amount = i128;
+
+/// 1 as an `Amount`
+pub const AMOUNT_ONE: Amount = 1_000_000_000_000_000_000;
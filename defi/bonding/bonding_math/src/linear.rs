@@ -0,0 +1,104 @@
+use num_traits::Zero;
+
+use crate::integral::IntegralCurve;
+use crate::number::*;
+use crate::Amount;
+
+/// price = slope * supply + initial_price
+#[derive(Clone, Debug)]
+pub struct LinearCurve {
+    slope: Number,
+    initial_price: Number,
+    precision_bits: u16,
+}
+
+impl LinearCurve {
+    pub fn new(slope: Amount, initial_price: Amount, precision_bits: u16) -> Self {
+        assert!(slope >= 0, "slope cannot be negative");
+        assert!(initial_price >= 0, "initial price cannot be negative");
+        assert!(
+            !(slope == 0 && initial_price == 0),
+            "slope and initial price cannot both be zero"
+        );
+        Self {
+            slope: number_from_amount(slope, precision_bits),
+            initial_price: number_from_amount(initial_price, precision_bits),
+            precision_bits,
+        }
+    }
+}
+
+impl IntegralCurve for LinearCurve {
+    fn precision_bits(&self) -> u16 {
+        self.precision_bits
+    }
+
+    fn price_at(&self, s: &Number) -> Number {
+        mul(&self.slope, s, self.precision_bits) + &self.initial_price
+    }
+
+    fn reserve_at(&self, s: &Number) -> Number {
+        // slope * s^2 / 2 + initial_price * s
+        let bits = self.precision_bits;
+        let s_squared = mul(s, s, bits);
+        div(
+            &mul(&self.slope, &s_squared, bits),
+            &number_from_int(2, bits),
+            bits,
+        ) + mul(&self.initial_price, s, bits)
+    }
+
+    fn supply_at(&self, r: &Number) -> Number {
+        // positive root of slope / 2 * s^2 + initial_price * s - r = 0
+        // = (sqrt(initial_price^2 + 2 * slope * r) - initial_price) / slope
+        let bits = self.precision_bits;
+        if self.slope == Number::zero() {
+            return div(r, &self.initial_price, bits);
+        }
+        let two_slope_r = mul(&mul(&number_from_int(2, bits), &self.slope, bits), r, bits);
+        let discriminant = mul(&self.initial_price, &self.initial_price, bits) + two_slope_r;
+        div(
+            &(sqrt(&discriminant, bits) - &self.initial_price),
+            &self.slope,
+            bits,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::integral::assert_round_trips_never_profit;
+    use crate::AMOUNT_ONE;
+
+    #[test]
+    fn test_buy_from_zero() {
+        // price = s, so 50 collateral buys sqrt(2 * 50) = 10
+        let curve = LinearCurve::new(AMOUNT_ONE, 0, 384);
+        let minted = curve.get_initial_supply(50 * AMOUNT_ONE);
+        assert!(minted <= 10 * AMOUNT_ONE);
+        assert!(10 * AMOUNT_ONE - minted <= 1);
+        assert_eq!(curve.get_price(0, 10 * AMOUNT_ONE), 10 * AMOUNT_ONE);
+    }
+
+    #[test]
+    fn test_flat_curve() {
+        let curve = LinearCurve::new(0, 2 * AMOUNT_ONE, 384);
+        let minted = curve.get_mint_amount(300 * AMOUNT_ONE, 1000 * AMOUNT_ONE, 500 * AMOUNT_ONE);
+        assert_eq!(minted, 150 * AMOUNT_ONE);
+        let returned = curve.get_return_amount(150 * AMOUNT_ONE, 1300 * AMOUNT_ONE, 650 * AMOUNT_ONE);
+        assert_eq!(returned, 300 * AMOUNT_ONE);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        assert_round_trips_never_profit(&LinearCurve::new(AMOUNT_ONE / 1000, AMOUNT_ONE / 2, 384));
+        assert_round_trips_never_profit(&LinearCurve::new(3 * AMOUNT_ONE, 0, 384));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_flat_zero_curve_is_invalid() {
+        LinearCurve::new(0, 0, 384);
+    }
+}
@@ -0,0 +1,290 @@
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::{Amount, AMOUNT_ONE};
+
+#[cfg(not(feature = "use_rationals"))]
+mod details {
+    pub type Number = num_bigint::BigInt;
+
+    #[inline(always)]
+    pub fn bigint_to_number(b: num_bigint::BigInt, precision_bits: u16) -> Number {
+        b << precision_bits
+    }
+
+    #[inline(always)]
+    pub fn bigint_from_number(b: Number, precision_bits: u16) -> num_bigint::BigInt {
+        // let b = b >> precision_bits; // truncate isn't great, but it's easy
+        // round instead (could probably be optimized)
+
+        if precision_bits > 0 {
+            let mut b = b >> (precision_bits - 1);
+            if (b.clone() % 2) == 1.into() {
+                b += 1;
+            }
+            b >> 1
+        } else {
+            b
+        }
+    }
+
+    #[inline(always)]
+    pub fn bigint_from_number_floor(b: Number, precision_bits: u16) -> num_bigint::BigInt {
+        b >> precision_bits
+    }
+
+    #[inline(always)]
+    pub fn pow_nd(base: &Number, n: u32, d: u32) -> Number {
+        base.pow(n).nth_root(d)
+    }
+
+    #[inline(always)]
+    pub fn limit_precision(b: Number) -> Number {
+        // already bounded by precision_bits
+        b
+    }
+}
+
+#[cfg(feature = "use_rationals")]
+mod details {
+    use num_rational::BigRational;
+    pub type Number = num_rational::BigRational;
+
+    #[inline(always)]
+    pub fn bigint_to_number(b: num_bigint::BigInt, _precision_bits: u16) -> Number {
+        BigRational::new(b, 1_000_000_000_000_000_000u128.into())
+    }
+
+    #[inline(always)]
+    pub fn bigint_from_number(b: Number, _precision_bits: u16) -> num_bigint::BigInt {
+        let multiple = BigRational::new(1_000_000_000_000_000_000u128.into(), 1.into());
+        // well, to_integer() docs say " /// Converts to an integer, rounding towards zero."
+        // but really want half-round away from zero
+        // (b * multiple).to_integer()
+        // so use .round() instead
+        (b * multiple).round().numer().clone()
+    }
+
+    pub fn bigint_from_number_floor(b: Number, _precision_bits: u16) -> num_bigint::BigInt {
+        let multiple = BigRational::new(1_000_000_000_000_000_000u128.into(), 1.into());
+        (b * multiple).floor().numer().clone()
+    }
+
+    pub fn pow_nd(base: &Number, n: u32, d: u32) -> Number {
+        let r = base.pow(n as i32);
+        // nth_root will round so we still need to explicitly add floating point precision here
+        // let's use 1e-10 ** x for both numerator and denominator
+        // experimentally checked
+        let multiple: num_bigint::BigInt = 1_000_000_000_000_000_000u128.into();
+        let multiple = multiple.pow(d + 2); // extra bits 60 * (d + 2) enough precision
+        let numer = r.numer() * &multiple;
+        let denom = r.denom() * multiple;
+        let numer_root = numer.nth_root(d);
+        let denom_root = denom.nth_root(d);
+        BigRational::new(numer_root, denom_root)
+    }
+
+    pub fn limit_precision(b: Number) -> Number {
+        // iterative approximations would grow numerator and denominator without bound
+        // so round intermediate results to 1e-54, far beyond the 1e-18 of an Amount
+        let multiple: num_bigint::BigInt = 1_000_000_000_000_000_000u128.into();
+        let multiple = multiple.pow(3);
+        let numer = (b * BigRational::from_integer(multiple.clone())).round().numer().clone();
+        BigRational::new(numer, multiple)
+    }
+}
+
+pub use details::Number;
+
+pub fn amount_from_number(b: Number, precision_bits: u16) -> Option<Amount> {
+    // convert from BigInt with Amount precision
+    details::bigint_from_number(b, precision_bits).to_i128()
+}
+
+/// Like `amount_from_number` but rounds towards negative infinity, for amounts paid out which must never be
+/// more than the exact result
+pub fn amount_from_number_floor(b: Number, precision_bits: u16) -> Option<Amount> {
+    details::bigint_from_number_floor(b, precision_bits).to_i128()
+}
+
+pub fn number_from_amount(a: Amount, precision_bits: u16) -> Number {
+    details::bigint_to_number(a.into(), precision_bits)
+}
+
+pub fn scaled_power(
+    scale: &Number,
+    base_n: &Number,
+    base_d: &Number,
+    exp_n: u32,
+    exp_d: u32,
+) -> Number {
+    let n = details::pow_nd(base_n, exp_n, exp_d);
+    let d = details::pow_nd(base_d, exp_n, exp_d);
+
+    assert!(d != Number::zero(), "scaled_power divide by zero"); // nicer error message
+    scale * n / d
+}
+
+/// Number of terms used by the `exp` and `ln` series.  Their arguments are range reduced first so this gets well past
+/// the precision of an Amount
+const SERIES_TERMS: u32 = 64;
+
+pub fn number_one(precision_bits: u16) -> Number {
+    number_from_amount(AMOUNT_ONE, precision_bits)
+}
+
+pub fn number_from_int(i: i64, precision_bits: u16) -> Number {
+    number_from_amount(i128::from(i) * AMOUNT_ONE, precision_bits)
+}
+
+/// a * b, keeping the fixed point scale when built without `use_rationals`
+pub fn mul(a: &Number, b: &Number, precision_bits: u16) -> Number {
+    a * b / number_one(precision_bits)
+}
+
+/// a / b, keeping the fixed point scale when built without `use_rationals`
+pub fn div(a: &Number, b: &Number, precision_bits: u16) -> Number {
+    assert!(*b != Number::zero(), "div divide by zero"); // nicer error message
+    a * number_one(precision_bits) / b
+}
+
+pub fn sqrt(x: &Number, precision_bits: u16) -> Number {
+    assert!(!x.is_negative(), "sqrt of negative number");
+    let one = number_one(precision_bits);
+    scaled_power(&one, x, &one, 1, 2)
+}
+
+pub fn exp(x: &Number, precision_bits: u16) -> Number {
+    let one = number_one(precision_bits);
+    let two = number_from_int(2, precision_bits);
+    let half = div(&one, &two, precision_bits);
+
+    // e^x = (e^(x / 2^k))^(2^k) with |x / 2^k| <= 1/2 so the Taylor series converges quickly
+    let mut y = x.clone();
+    let mut k = 0;
+    while y.abs() > half {
+        y = div(&y, &two, precision_bits);
+        k += 1;
+    }
+
+    let mut term = one.clone();
+    let mut sum = one;
+    for i in 1..SERIES_TERMS {
+        let i = number_from_int(i.into(), precision_bits);
+        term = details::limit_precision(div(&mul(&term, &y, precision_bits), &i, precision_bits));
+        sum += &term;
+    }
+
+    for _ in 0..k {
+        sum = details::limit_precision(mul(&sum, &sum, precision_bits));
+    }
+    sum
+}
+
+pub fn ln(x: &Number, precision_bits: u16) -> Number {
+    assert!(*x > Number::zero(), "ln of non-positive number");
+    let one = number_one(precision_bits);
+    let two = number_from_int(2, precision_bits);
+
+    // x = m * 2^e with 1 <= m < 2
+    let mut m = x.clone();
+    let mut e: i64 = 0;
+    while m >= two {
+        m = div(&m, &two, precision_bits);
+        e += 1;
+    }
+    while m < one {
+        m = mul(&m, &two, precision_bits);
+        e -= 1;
+    }
+
+    let ln_2 = ln_reduced(&two, precision_bits);
+    ln_reduced(&m, precision_bits) + mul(&ln_2, &number_from_int(e, precision_bits), precision_bits)
+}
+
+/// ln(m) = 2 * atanh((m - 1) / (m + 1)) which converges quickly for 1 <= m <= 2
+fn ln_reduced(m: &Number, precision_bits: u16) -> Number {
+    let one = number_one(precision_bits);
+    let z = div(&(m - &one), &(m + &one), precision_bits);
+    let z_squared = details::limit_precision(mul(&z, &z, precision_bits));
+
+    let mut power = z.clone();
+    let mut sum = z;
+    for i in 1..SERIES_TERMS {
+        let d = number_from_int((2 * i + 1).into(), precision_bits);
+        power = details::limit_precision(mul(&power, &z_squared, precision_bits));
+        sum += details::limit_precision(div(&power, &d, precision_bits));
+    }
+    mul(&sum, &number_from_int(2, precision_bits), precision_bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::details::bigint_from_number;
+    use super::details::bigint_to_number;
+    use super::*;
+
+    #[test]
+    fn test_amount_from_bigint() {
+        let precision_bits = 10;
+        let i: i128 = -25;
+        let b: num_bigint::BigInt = i.into();
+        let b = bigint_to_number(b, precision_bits);
+        let maybe_a = amount_from_number(b, precision_bits);
+        assert_eq!(maybe_a, Some(-25)) // -0.000000000000000025
+    }
+
+    #[test]
+    fn test_bigint_from_amount() {
+        let precision_bits = 0;
+        let i: i128 = -25;
+        let b = number_from_amount(i, precision_bits);
+        let b = bigint_from_number(b, precision_bits);
+        assert_eq!(b.to_i128().unwrap(), i);
+
+        let precision_bits = 2;
+        let b = number_from_amount(i, precision_bits);
+        let b = bigint_from_number(b, precision_bits);
+        assert_eq!(b.to_i128().unwrap(), i);
+    }
+
+    #[test]
+    fn test_amount_overflow() {
+        let precision_bits = 384;
+        let max = number_from_amount(i128::MAX, precision_bits);
+        let too_big = max.clone() + max;
+        assert_eq!(amount_from_number(too_big, precision_bits), None);
+    }
+
+    #[test]
+    fn test_sqrt_exp_ln() {
+        let precision_bits = 384;
+        let two = number_from_int(2, precision_bits);
+
+        let a = amount_from_number(sqrt(&two, precision_bits), precision_bits).unwrap();
+        assert_eq!(a, 1_414213562373095049); // 1.414213562373095049
+
+        let a = amount_from_number(exp(&two, precision_bits), precision_bits).unwrap();
+        assert_eq!(a, 7_389056098930650227); // 7.389056098930650227
+
+        let a = amount_from_number(ln(&two, precision_bits), precision_bits).unwrap();
+        assert_eq!(a, 693147180559945309); // 0.693147180559945309
+
+        let x = number_from_amount(12345_678900000000000000, precision_bits); // 12345.6789
+        let a = amount_from_number(exp(&ln(&x, precision_bits), precision_bits), precision_bits).unwrap();
+        assert_eq!(a, 12345_678900000000000000);
+    }
+
+    #[test]
+    fn test_amount_from_number_floor() {
+        let precision_bits = 384;
+        let two_thirds = div(
+            &number_from_int(2, precision_bits),
+            &number_from_int(3, precision_bits),
+            precision_bits,
+        );
+        let rounded = amount_from_number(two_thirds.clone(), precision_bits).unwrap();
+        let floored = amount_from_number_floor(two_thirds, precision_bits).unwrap();
+        assert_eq!(rounded, 666666666666666667);
+        assert_eq!(floored, 666666666666666666);
+    }
+}
@@ -0,0 +1,282 @@
+//! Bancor style curves parametrized by "curve weight" aka "reserve ratio" `reserve_ratio_n / reserve_ratio_d`,
+//! computed with max precision within an Amount (ie. precise, but unoptimized)
+
+use num_traits::Zero;
+
+use crate::number::*;
+use crate::Amount;
+
+fn calculate_price(
+    r: Number,
+    s: Number,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+    precision_bits: u16,
+) -> Number {
+    //  Continuous Token Price = Reserve Token Balance / (Continuous Token Supply x Reserve Ratio)
+    // n and d are plain integers so they don't need the fixed point scale
+    let n = number_from_int(reserve_ratio_n.into(), 0);
+    let d = number_from_int(reserve_ratio_d.into(), 0);
+    div(&r, &s, precision_bits) * d / n
+}
+
+fn calculate_initial_supply(
+    collateral_amount: Number,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+) -> Number {
+    //collateral_amount * reserve_ratio_d / reserve_ratio_n
+    scaled_power(
+        &collateral_amount,
+        &number_from_int(reserve_ratio_d.into(), 0),
+        &number_from_int(reserve_ratio_n.into(), 0),
+        1,
+        1,
+    )
+}
+
+fn calculate_curve_mint(
+    c: Number,
+    r: Number,
+    s: Number,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+) -> Number {
+    // PurchaseReturn = ContinuousTokenSupply * ((1 + ReserveTokensReceived / ReserveTokenBalance) ^ (ReserveRatio) - 1)
+    // = s * (((1 + c / r) ^ rr) - 1)
+
+    //let result = s * (n / d - 1); // worse for precision
+    //let result = s.clone() * n / d - s; // better for precision
+    // scaled_power used here for arbitrary precision way to do: s * (1 + c/r)^rr
+    scaled_power(&s, &(c + &r), &r, reserve_ratio_n, reserve_ratio_d) - s
+}
+
+fn calculate_curve_return(
+    c: Number,
+    r: Number,
+    s: Number,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+) -> Number {
+    // SaleReturn = ReserveTokenBalance * (1 - (1 - ContinuousTokensReceived / ContinuousTokenSupply) ^ (1 / (ReserveRatio)))
+    // = r * (1 - (1 - c / s) ^ (1/rr))
+    // = r * (1 - ([s - c] / s)^rri) = r * (1-(n/d))
+
+    // let result = r * (1 - n / d); // bad for precision
+    // let result = r - r*(n/d); // better for precision
+    // let result = r - ((r * n) / d); // best precision
+    // scaled_power used here for arbitrary precision way to do: r * (1 - c/s)^(1/rr)
+    &r - scaled_power(&r, &(&s - c), &s, reserve_ratio_d, reserve_ratio_n)
+}
+
+pub fn get_initial_supply(
+    collateral_amount: Amount,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+    precision_bits: u16,
+) -> Amount {
+    assert!(collateral_amount >= 0);
+    assert!(reserve_ratio_d != 0);
+
+    if collateral_amount == 0 {
+        return 0;
+    }
+
+    let collateral_amount = number_from_amount(collateral_amount, precision_bits);
+
+    let result = calculate_initial_supply(collateral_amount, reserve_ratio_n, reserve_ratio_d);
+
+    amount_from_number(result, precision_bits).unwrap()
+}
+
+pub fn get_mint_amount(
+    collateral_amount: Amount,
+    reserve_amount: Amount,
+    supply_amount: Amount,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+    precision_bits: u16,
+) -> Amount {
+    assert!(collateral_amount >= 0);
+    assert!(reserve_amount >= 0);
+    assert!(supply_amount >= 0);
+    assert!(reserve_ratio_d != 0);
+
+    if collateral_amount == 0 {
+        return 0;
+    }
+
+    let c = number_from_amount(collateral_amount, precision_bits);
+    let r = number_from_amount(reserve_amount, precision_bits);
+    let s = number_from_amount(supply_amount, precision_bits);
+
+    let result = calculate_curve_mint(c, r, s, reserve_ratio_n, reserve_ratio_d);
+
+    assert!(result >= Number::zero(), "Calculated negative mint amount");
+
+    amount_from_number(result, precision_bits).unwrap()
+}
+
+pub fn get_return_amount(
+    continuous_amount: Amount,
+    reserve_amount: Amount,
+    supply_amount: Amount,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+    precision_bits: u16,
+) -> Amount {
+    assert!(continuous_amount >= 0);
+    assert!(reserve_amount >= 0);
+    assert!(supply_amount >= 0);
+    assert!(reserve_ratio_d != 0);
+
+    if continuous_amount == 0 {
+        return 0;
+    }
+
+    let c = number_from_amount(continuous_amount, precision_bits);
+    let r = number_from_amount(reserve_amount, precision_bits);
+    let s = number_from_amount(supply_amount, precision_bits);
+
+    let result = calculate_curve_return(c, r, s, reserve_ratio_n, reserve_ratio_d);
+
+    assert!(
+        result >= Number::zero(),
+        "Calculated negative return amount"
+    );
+
+    amount_from_number(result, precision_bits).unwrap()
+}
+
+pub fn get_price(
+    reserve_amount: Amount,
+    supply_amount: Amount,
+    reserve_ratio_n: u32,
+    reserve_ratio_d: u32,
+    precision_bits: u16,
+) -> Amount {
+    assert!(reserve_amount >= 0);
+    assert!(supply_amount > 0, "Price is undefined without supply");
+    assert!(reserve_ratio_n != 0);
+
+    let r = number_from_amount(reserve_amount, precision_bits);
+    let s = number_from_amount(supply_amount, precision_bits);
+    let p = calculate_price(r, s, reserve_ratio_n, reserve_ratio_d, precision_bits);
+    amount_from_number(p, precision_bits).unwrap()
+}
+
+// -------- Testing
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_1_buy() {
+        let precision_bits = 384;
+        let collateral_amount = 300_000000000000000000;
+        let reserve_amount = 60000_000000000000000000;
+        let supply_amount = 300000_000000000000000000;
+        let reserve_ratio_n = 1;
+        let reserve_ratio_d = 5;
+        let to_mint = get_mint_amount(
+            collateral_amount,
+            reserve_amount,
+            supply_amount,
+            reserve_ratio_n,
+            reserve_ratio_d,
+            precision_bits,
+        );
+        let expected: i128 = 299401793723844635041; // 299.401793723844635041 // the right answer to 18 decimal places (the default for Decimal)
+                assert_eq!(to_mint, expected);
+
+        // buy more
+        let collateral_amount = 700_000000000000000000;
+        let reserve_amount = 60300_000000000000000000;
+        let supply_amount = 300000000000000000000000i128 + 299401793723844635041i128;
+        let reserve_ratio_n = 1;
+        let reserve_ratio_d = 5;
+        let to_mint = get_mint_amount(
+            collateral_amount,
+            reserve_amount,
+            supply_amount,
+            reserve_ratio_n,
+            reserve_ratio_d,
+            precision_bits,
+        );
+        let expected: i128 = 693997438220660073726; // 693.997438220660073726  // the right answer to 18 decimal places (the default for Decimal
+                assert_eq!(to_mint, expected);
+    }
+
+    #[test]
+    fn test_2_buy_sell() {
+        let precision_bits = 384;
+        let collateral_amount = 300_000000000000000000;
+        let reserve_amount = 60000_000000000000000000;
+        let supply_amount = 300000_000000000000000000;
+        let reserve_ratio_n = 1;
+        let reserve_ratio_d = 5;
+        let to_mint = get_mint_amount(
+            collateral_amount,
+            reserve_amount,
+            supply_amount,
+            reserve_ratio_n,
+            reserve_ratio_d,
+            precision_bits,
+        );
+        let expected: i128 = 299401793723844635041; // 299.401793723844635041 // the right answer to 18 decimal places (the default for Decimal)
+                assert_eq!(to_mint, expected);
+
+        // sell back same
+        let continuous_amount = expected;
+        let reserve_amount = 60300_000000000000000000;
+        let supply_amount = 300000000000000000000000i128 + 299401793723844635041i128;
+        let reserve_ratio_n = 1;
+        let reserve_ratio_d = 5;
+        let to_return = get_return_amount(
+            continuous_amount,
+            reserve_amount,
+            supply_amount,
+            reserve_ratio_n,
+            reserve_ratio_d,
+            precision_bits,
+        );
+        let expected: i128 = 300000000000000000000; // 300.0 // the right answer to 18 decimal places (the default for Decimal
+                assert_eq!(to_return, expected);
+    }
+
+    #[test]
+    fn test_3_sell() {
+        // sell some
+        let precision_bits = 384;
+        let continuous_amount = 100_000000000000000000;
+        let reserve_amount = 61000_000000000000000000;
+        let supply_amount =
+            300000000000000000000000i128 + 299401793723844635041i128 + 693997438220660073726i128; // amount after test_1
+        let reserve_ratio_n = 1;
+        let reserve_ratio_d = 5;
+        let to_return = get_return_amount(
+            continuous_amount,
+            reserve_amount,
+            supply_amount,
+            reserve_ratio_n,
+            reserve_ratio_d,
+            precision_bits,
+        );
+        let expected: i128 = 101263817029251588263; // 101.263817029251588263 // the right answer to 18 decimal places (the default for Decimal
+                assert_eq!(to_return, expected);
+    }
+
+    #[test]
+    fn test_4_price() {
+        let precision_bits = 384;
+        // price = r / (s * rr) = 60000 / (300000 / 5) = 1
+        let price = get_price(
+            60000_000000000000000000,
+            300000_000000000000000000,
+            1,
+            5,
+            precision_bits,
+        );
+        assert_eq!(price, 1_000000000000000000);
+    }
+}
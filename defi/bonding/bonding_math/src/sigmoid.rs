@@ -0,0 +1,115 @@
+use num_traits::Zero;
+
+use crate::integral::IntegralCurve;
+use crate::number::*;
+use crate::Amount;
+
+/// S-curve rising from 0 towards a capped price:
+/// price = cap / 2 * (1 + (supply - midpoint) / sqrt((supply - midpoint)^2 + spread))
+///
+/// The price is half the cap at the midpoint.  The larger the spread the flatter the curve, at midpoint +/- sqrt(spread)
+/// the price is about 15% / 85% of the cap.
+#[derive(Clone, Debug)]
+pub struct SigmoidCurve {
+    cap: Number,
+    midpoint: Number,
+    spread: Number,
+    precision_bits: u16,
+}
+
+impl SigmoidCurve {
+    pub fn new(cap: Amount, midpoint: Amount, spread: Amount, precision_bits: u16) -> Self {
+        assert!(cap > 0, "cap must be positive");
+        assert!(midpoint >= 0, "midpoint cannot be negative");
+        assert!(spread > 0, "spread must be positive");
+        Self {
+            cap: number_from_amount(cap, precision_bits),
+            midpoint: number_from_amount(midpoint, precision_bits),
+            spread: number_from_amount(spread, precision_bits),
+            precision_bits,
+        }
+    }
+
+    /// sqrt((s - midpoint)^2 + spread)
+    fn hypotenuse(&self, s: &Number) -> Number {
+        let bits = self.precision_bits;
+        let u = s - &self.midpoint;
+        sqrt(&(mul(&u, &u, bits) + &self.spread), bits)
+    }
+}
+
+impl IntegralCurve for SigmoidCurve {
+    fn precision_bits(&self) -> u16 {
+        self.precision_bits
+    }
+
+    fn price_at(&self, s: &Number) -> Number {
+        let bits = self.precision_bits;
+        let half_cap = div(&self.cap, &number_from_int(2, bits), bits);
+        let u = s - &self.midpoint;
+        let shape = number_one(bits) + div(&u, &self.hypotenuse(s), bits);
+        mul(&half_cap, &shape, bits)
+    }
+
+    fn reserve_at(&self, s: &Number) -> Number {
+        // cap / 2 * (s + sqrt((s - midpoint)^2 + spread) - sqrt(midpoint^2 + spread))
+        let bits = self.precision_bits;
+        let half_cap = div(&self.cap, &number_from_int(2, bits), bits);
+        let area = s + self.hypotenuse(s) - self.hypotenuse(&Number::zero());
+        mul(&half_cap, &area, bits)
+    }
+
+    fn supply_at(&self, r: &Number) -> Number {
+        // with u = s - midpoint and w = 2 * r / cap + sqrt(midpoint^2 + spread) - midpoint,
+        // u + sqrt(u^2 + spread) = w  =>  u = (w^2 - spread) / (2 * w)
+        let bits = self.precision_bits;
+        let two = number_from_int(2, bits);
+        let w = div(&mul(&two, r, bits), &self.cap, bits) + self.hypotenuse(&Number::zero())
+            - &self.midpoint;
+        let u = div(&(mul(&w, &w, bits) - &self.spread), &mul(&two, &w, bits), bits);
+        u + &self.midpoint
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::integral::assert_round_trips_never_profit;
+    use crate::AMOUNT_ONE;
+
+    fn curve() -> SigmoidCurve {
+        SigmoidCurve::new(10 * AMOUNT_ONE, 1000 * AMOUNT_ONE, 10000 * AMOUNT_ONE, 384)
+    }
+
+    #[test]
+    fn test_price_is_capped() {
+        let curve = curve();
+        assert_eq!(curve.get_price(0, 1000 * AMOUNT_ONE), 5 * AMOUNT_ONE);
+        assert!(curve.get_price(0, 0) < AMOUNT_ONE / 20);
+        let price = curve.get_price(0, 1_000_000 * AMOUNT_ONE);
+        assert!(price < 10 * AMOUNT_ONE);
+        assert!(price > 9_999900000000000000); // 9.9999
+    }
+
+    #[test]
+    fn test_buy_from_zero_matches_reserve() {
+        let curve = curve();
+        let minted = curve.get_initial_supply(5000 * AMOUNT_ONE);
+        let s = number_from_amount(minted, 384);
+        let reserve = amount_from_number(curve.reserve_at(&s), 384).unwrap();
+        assert!(reserve <= 5000 * AMOUNT_ONE);
+        assert!(5000 * AMOUNT_ONE - reserve <= 100);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        assert_round_trips_never_profit(&curve());
+        assert_round_trips_never_profit(&SigmoidCurve::new(AMOUNT_ONE / 4, 0, AMOUNT_ONE / 100, 384));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_cap_is_invalid() {
+        SigmoidCurve::new(0, 1000 * AMOUNT_ONE, 10000 * AMOUNT_ONE, 384);
+    }
+}
@@ -0,0 +1,103 @@
+// Property tests for whichever precision backend the crate is built with, checking the results against exact BigInt
+// arithmetic.  Run with and without `--no-default-features` to cover both.
+
+use bonding_math::exponential::ExponentialCurve;
+use bonding_math::integral::{buy_then_sell, IntegralCurve};
+use bonding_math::linear::LinearCurve;
+use bonding_math::ratio;
+use bonding_math::sigmoid::SigmoidCurve;
+use bonding_math::{Amount, AMOUNT_ONE};
+use num_bigint::BigInt;
+use proptest::prelude::*;
+
+const PRECISION_BITS: u16 = 384;
+
+fn big(a: Amount) -> BigInt {
+    BigInt::from(a)
+}
+
+/// whole amounts from 1 up to 1e9, with up to 18 decimal places
+fn amount() -> impl Strategy<Value = Amount> {
+    AMOUNT_ONE..1_000_000_000 * AMOUNT_ONE
+}
+
+/// reserve ratio n / d with 0 < n <= d
+fn reserve_ratio() -> impl Strategy<Value = (u32, u32)> {
+    (1u32..=5).prop_flat_map(|d| (1u32..=d, Just(d)))
+}
+
+proptest! {
+    #[test]
+    fn ratio_mint_matches_reference(
+        c in amount(),
+        r in amount(),
+        s in amount(),
+        (n, d) in reserve_ratio(),
+    ) {
+        let m = ratio::get_mint_amount(c, r, s, n, d, PRECISION_BITS);
+
+        // s * ((1 + c / r)^(n / d) - 1) = m  <=>  (s + m)^d * r^n = s^d * (r + c)^n
+        // so the exact answer lies within one unit of the last place of m
+        let target = big(s).pow(d) * big(r + c).pow(n);
+        prop_assert!(big(s + m - 1).pow(d) * big(r).pow(n) <= target);
+        prop_assert!(big(s + m + 1).pow(d) * big(r).pow(n) >= target);
+    }
+
+    #[test]
+    fn ratio_return_matches_reference(
+        r in amount(),
+        s in amount(),
+        fraction in 1u32..=1000,
+        (n, d) in reserve_ratio(),
+    ) {
+        let c = s / 1000 * Amount::from(fraction);
+        let ret = ratio::get_return_amount(c, r, s, n, d, PRECISION_BITS);
+
+        // r * (1 - (1 - c / s)^(d / n)) = ret  <=>  (r - ret)^n * s^d = r^n * (s - c)^d
+        let target = big(r).pow(n) * big(s - c).pow(d);
+        prop_assert!(big(r - ret - 1).pow(n) * big(s).pow(d) <= target);
+        prop_assert!(big(r - ret + 1).pow(n) * big(s).pow(d) >= target);
+    }
+}
+
+fn assert_round_trip<C: IntegralCurve>(curve: &C, c: Amount, s: Amount) -> Result<(), TestCaseError> {
+    let returned = buy_then_sell(curve, c, s);
+    prop_assert!(returned <= c, "round trip of {} at supply {} returned {}", c, s, returned);
+    Ok(())
+}
+
+proptest! {
+    // the integral curves use series approximations which are slow with rationals, so keep the case count down
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn linear_round_trip_never_profits(
+        slope in 0..10 * AMOUNT_ONE,
+        initial_price in 1..10 * AMOUNT_ONE,
+        c in 1..1_000_000 * AMOUNT_ONE,
+        s in 0..1_000_000 * AMOUNT_ONE,
+    ) {
+        assert_round_trip(&LinearCurve::new(slope, initial_price, PRECISION_BITS), c, s)?;
+    }
+
+    #[test]
+    fn exponential_round_trip_never_profits(
+        initial_price in 1..10 * AMOUNT_ONE,
+        growth_rate in 1..AMOUNT_ONE / 10000,
+        c in 1..1_000_000 * AMOUNT_ONE,
+        s in 0..100_000 * AMOUNT_ONE,
+    ) {
+        assert_round_trip(&ExponentialCurve::new(initial_price, growth_rate, PRECISION_BITS), c, s)?;
+    }
+
+    #[test]
+    fn sigmoid_round_trip_never_profits(
+        cap in 1..100 * AMOUNT_ONE,
+        midpoint in 0..1_000_000 * AMOUNT_ONE,
+        spread in 1..1_000_000_000 * AMOUNT_ONE,
+        c in 1..1_000_000 * AMOUNT_ONE,
+        s in 0..1_000_000 * AMOUNT_ONE,
+    ) {
+        assert_round_trip(&SigmoidCurve::new(cap, midpoint, spread, PRECISION_BITS), c, s)?;
+    }
+}
@@ -4,6 +4,7 @@ use bonding_macros::blueprint_stub; // fancy proc macro that generates a "hidden
 
 // the expected inferface, but for the moment "impl Trait...." cannot be done inside a blueprint
 // use blueprint_stub to generate an empty blueprint so we can get the ABI stubs for use from other blueprints
+// along with `BondingCurveClient` for calling any component implementing it
 #[blueprint_stub]
 pub trait BondingCurve {
     fn get_mint_amount(
@@ -0,0 +1,107 @@
+use scrypto::prelude::*;
+
+use bonding_math::integral::IntegralCurve;
+use bonding_math::exponential::ExponentialCurve;
+
+use crate::number::*;
+
+blueprint! {
+    struct ExponentialBondingCurve {
+        initial_price: Decimal,
+        growth_rate: Decimal,
+        precision_bits: u16,
+    }
+
+    impl ExponentialBondingCurve {
+        pub fn new(initial_price: Decimal, growth_rate: Decimal, precision_bits: u16) -> ComponentAddress {
+            debug!(
+                "ExponentialBondingCurve::new called with initial price {} growth rate {} @ {} bits",
+                initial_price, growth_rate, precision_bits
+            );
+            // validate the parameters
+            ExponentialCurve::new(
+                amount_from_decimal(initial_price),
+                amount_from_decimal(growth_rate),
+                precision_bits,
+            );
+            Self {
+                initial_price,
+                growth_rate,
+                precision_bits,
+            }
+            .instantiate()
+            .globalize()
+        }
+
+        // can't do trait impl's within the blueprint.  Would be ncie to get those compile time checks...
+        // impl BondingCurve for blueprint::ExponentialBondingCurve {
+
+        pub fn get_initial_supply(&self, collateral_amount: Decimal) -> Decimal {
+            decimal_from_amount(
+                self.curve()
+                    .get_initial_supply(amount_from_decimal(collateral_amount)),
+            )
+        }
+
+        pub fn get_mint_amount(
+            &self,
+            collateral_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            decimal_from_amount(self.curve().get_mint_amount(
+                amount_from_decimal(collateral_amount),
+                amount_from_decimal(reserve_amount),
+                amount_from_decimal(supply_amount),
+            ))
+        }
+
+        pub fn get_return_amount(
+            &self,
+            continuous_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            decimal_from_amount(self.curve().get_return_amount(
+                amount_from_decimal(continuous_amount),
+                amount_from_decimal(reserve_amount),
+                amount_from_decimal(supply_amount),
+            ))
+        }
+
+        pub fn get_price(&self, reserve_amount: Decimal, supply_amount: Decimal) -> Decimal {
+            decimal_from_amount(self.curve().get_price(
+                amount_from_decimal(reserve_amount),
+                amount_from_decimal(supply_amount),
+            ))
+        }
+
+        // }
+
+        pub fn get_sale_quote(
+            &self,
+            continuous_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            self.get_return_amount(continuous_amount, reserve_amount, supply_amount)
+        }
+
+        pub fn get_buy_quote(
+            &self,
+            collateral_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            self.get_mint_amount(collateral_amount, reserve_amount, supply_amount)
+        }
+
+        fn curve(&self) -> ExponentialCurve {
+            ExponentialCurve::new(
+                amount_from_decimal(self.initial_price),
+                amount_from_decimal(self.growth_rate),
+                self.precision_bits,
+            )
+        }
+    }
+}
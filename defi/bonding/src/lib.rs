@@ -4,13 +4,24 @@
 //! bonding curve defined by a plugable second component.  It will instantiate a component from the `RatioBondingCurve`
 //! blueprint if no other curve component is provided.
 //!
+//! `buy` and `sell` take the minimum amount the caller is willing to receive.  When the curve gives less, the sent in
+//! bucket is returned untouched instead of failing the transaction.  `new_with_fee` adds an entry/exit fee taken from
+//! every buy and sell into a separate vault, along with a badge for the beneficiary to `withdraw_fees` or `set_fee_rate`.
+//! The `get_buy_quote_amount` and `get_sell_quote_amount` methods return the plain amounts a trade would receive, net of fees.
+//!
 //! There is also an extrememly simple `BasicBondingCurve` blueprint (more like a flat line) which could be used to implement
 //! a simple "wrapped" or "virtual" token from another.  Or it's a a good template for making your own curve component.
 //!
+//! `LinearBondingCurve`, `ExponentialBondingCurve` and `SigmoidBondingCurve` (an S-curve with a price cap) are ready to use
+//! alternatives.  They are defined by their price function with the reserve being the area under it, so buying and
+//! then selling right back returns at most what was paid.
+//!
 //! Some useful utilities are included too.  An internal crate is used to provide the `blueprint_stub` macro which
-//! automates creating stub functions from a trait so calling another component is ergonomic.  Also included and
-//! used for the `RatioBondingCurve` is a reusable arbitrary precision number implmentation that converts to/from Decimal
-//! It is precise but not yet optimized.  Bounded (BigInt) or unbounded (BigRational) precision is configurable with a feature flag.
+//! automates creating stub functions and a typed `<Trait>Client` from a trait so calling another component is ergonomic.  The curve
+//! math lives in the internal `bonding_math` crate, which has no Scrypto dependency so wallets and simulation tooling can
+//! compute the exact same quotes off-ledger.  It includes a reusable arbitrary precision number implmentation that converts
+//! to/from Decimal amounts.  It is precise but not yet optimized.  Bounded (BigInt) or unbounded (BigRational) precision is
+//! configurable with a feature flag.
 //!
 //! # Bonuses:
 //!
@@ -45,13 +56,18 @@
 //! The initial release is pre-Alexandria with expected (minimal) API incompatabilities
 //!
 mod basic_curve; // a simple flat "curve" 1:1 implementation as a reference
-mod bonding_curve;
+mod bonding_curve; // the trait for cross-blueprint calls for plugable curve math
 mod default_curve; // a complete non-production bonding curve implementation parametrizable by "curve weight" aka "reserve ratio".  Max precision within Decimal. (ie. precise, but unoptimized)
-mod number; // arbitrary precision math used in default_curve // the trait for cross-blueprint calls for plugable curve math
+mod exponential_curve; // price grows exponentially with the supply
+mod linear_curve; // price grows linearly with the supply
+mod number; // conversions to/from the amounts of the arbitrary precision math in bonding_math
+mod sigmoid_curve; // S-curve price which levels off at a cap
 
 use scrypto::prelude::*;
 use scrypto_statictypes::prelude::*; // Use https://github.com/devmannic/scrypto_statictypes
 
+use bonding_curve::BondingCurveClient;
+
 declare_resource!(RESERVE); // resource type used for the reserve pool, collateral for buys
 declare_resource!(CONTINUOUS); // resource type minted/burned by the bonding curve
 declare_resource!(AUTH); // resource type for authority (badges) for mint/burn and authentication
@@ -61,7 +77,9 @@ blueprint! {
         reserve: VaultOf<RESERVE>,
         continuous: VaultOf<CONTINUOUS>,
         continuous_auth: VaultOf<AUTH>,
-        bonding_curve: ComponentAddress, // plugable, does the math
+        bonding_curve: BondingCurveClient, // plugable, does the math
+        fee_rate: Decimal, // fraction of every buy and sell kept as a fee, eg. 0.003 for 0.3%
+        fees: VaultOf<RESERVE>, // collected fees, withdrawable by the fee beneficiary
     }
 
     impl BondingAMM {
@@ -89,6 +107,26 @@ blueprint! {
             )
         }
 
+        // Constructor with specified curve charging `fee_rate` of every buy and sell.  Also returns the fee beneficiary
+        // badge which is needed to `withdraw_fees` and `set_fee_rate`
+        pub fn new_with_fee(
+            initial_reserve: BucketOf<RESERVE>,
+            continuous_name: String,
+            continuous_symbol: String,
+            bonding_curve: ComponentAddress,
+            fee_rate: Decimal,
+        ) -> (ComponentAddress, BucketOf<CONTINUOUS>, Bucket) {
+            let (component, continuous, beneficiary_badge) = BondingAMM::instantiate_amm(
+                initial_reserve,
+                continuous_name,
+                continuous_symbol,
+                Some(bonding_curve),
+                fee_rate,
+                true,
+            );
+            (component, continuous, beneficiary_badge.unwrap())
+        }
+
         // Main constructor easily called from other blueprints
         pub fn new(
             initial_reserve: BucketOf<RESERVE>,
@@ -96,19 +134,39 @@ blueprint! {
             continuous_symbol: String,
             bonding_curve: Option<ComponentAddress>,
         ) -> (ComponentAddress, BucketOf<CONTINUOUS>) {
+            let (component, continuous, _) = BondingAMM::instantiate_amm(
+                initial_reserve,
+                continuous_name,
+                continuous_symbol,
+                bonding_curve,
+                Decimal::zero(),
+                false,
+            );
+            (component, continuous)
+        }
+
+        fn instantiate_amm(
+            mut initial_reserve: BucketOf<RESERVE>,
+            continuous_name: String,
+            continuous_symbol: String,
+            bonding_curve: Option<ComponentAddress>,
+            fee_rate: Decimal,
+            with_beneficiary: bool,
+        ) -> (ComponentAddress, BucketOf<CONTINUOUS>, Option<Bucket>) {
             // initial_reserve cannot be empty
             assert!(!initial_reserve.is_empty());
+            assert_fee_rate(fee_rate);
 
             // get the curve Component for the math, or create a default_curve with ratio 1:5 and 384 bit precision (which is plenty for the 1e-18 precision of a Decimal)
             // the 384 bit precision is not needed and ignored when built with feature=use_rationals
             let bonding_curve =
                 bonding_curve.unwrap_or_else(|| default_curve::RatioBondingCurve::new(1, 5, 384));
 
-            // use the generated stubs for calling methods on the Component (kind of like a virtual call aka dynamic dispatch, but it happens via the kernel)
-            let curve: crate::bonding_curve::BondingCurve = bonding_curve.clone().into();
+            // use the generated client for calling methods on the Component (kind of like a virtual call aka dynamic dispatch, but it happens via the kernel)
+            let bonding_curve = BondingCurveClient::new(bonding_curve);
 
             // calculate the initial_supply for the initial_reserve
-            let initial_supply = curve.get_initial_supply(initial_reserve.amount());
+            let initial_supply = bonding_curve.get_initial_supply(initial_reserve.amount());
 
             // setup auth/badges
             let continuous_auth: BucketOf<AUTH> = ResourceBuilder::new_fungible().divisibility(DIVISIBILITY_NONE)
@@ -129,22 +187,46 @@ blueprint! {
             let mut continuous = continuous_auth
                 .authorize(|| continuous_def.mint(initial_supply));
 
+            // only the fee beneficiary can collect fees or change the rate.  Without one the fee stays fixed
+            let beneficiary_badge = if with_beneficiary {
+                Some(
+                    ResourceBuilder::new_fungible()
+                        .divisibility(DIVISIBILITY_NONE)
+                        .metadata("name", "BondingAMM fee beneficiary")
+                        .initial_supply(1),
+                )
+            } else {
+                None
+            };
+            let beneficiary_rule = match &beneficiary_badge {
+                Some(badge) => rule!(require(badge.resource_address())),
+                None => rule!(deny_all),
+            };
+            let access_rules = AccessRules::new()
+                .method("withdraw_fees", beneficiary_rule.clone())
+                .method("set_fee_rate", beneficiary_rule)
+                .default(rule!(allow_all));
+
             // store and instantiate
-            let component = Self {
+            let fees = VaultOf::with_bucket(initial_reserve.take(0));
+            let mut component = Self {
                 reserve: VaultOf::with_bucket(initial_reserve),
                 continuous: VaultOf::with_bucket(continuous.take(0)),
                 continuous_auth: VaultOf::with_bucket(continuous_auth),
                 bonding_curve,
+                fee_rate,
+                fees,
             }
-            .instantiate()
-            .globalize();
+            .instantiate();
+            component.add_access_check(access_rules);
+            let component = component.globalize();
 
-            (component, continuous)
+            (component, continuous, beneficiary_badge)
         }
 
         pub fn buy(
             &mut self,
-            collateral: BucketOf<RESERVE>,
+            mut collateral: BucketOf<RESERVE>,
             minimum_to_receive: Decimal,
         ) -> (BucketOf<CONTINUOUS>, BucketOf<RESERVE>) {
             // mint the right amount of CONTINOUS and return it
@@ -159,7 +241,8 @@ blueprint! {
                 return (self.continuous.take(0), collateral);
             }
 
-            // calculate the amount to mint
+            // calculate the amount to mint, net of the fee
+            let fee_amount = collateral.amount() * self.fee_rate;
             let mint_amount = self.get_buy_quote_amount(collateral.amount());
 
             debug!("will mint CONTINOUS amount: {}", mint_amount);
@@ -172,7 +255,8 @@ blueprint! {
                 return (self.continuous.take(0), collateral);
             }
 
-            // keep the collateral
+            // keep the fee and the collateral
+            self.fees.put(collateral.take(fee_amount));
             self.reserve.put(collateral);
 
             // mint for return
@@ -205,8 +289,10 @@ blueprint! {
                 debug!("returning CONTINUOUS amount: {}", continuous.amount());
                 return (self.reserve.take(0), continuous);
             }
-            // calculate the amount to return
-            let return_amount = self.get_sell_quote_amount(continuous.amount());
+            // calculate the amount to return, net of the fee
+            let gross_amount = self.get_gross_return_amount(continuous.amount());
+            let fee_amount = gross_amount * self.fee_rate;
+            let return_amount = gross_amount - fee_amount;
 
             debug!("will return RESERVE amount: {}", return_amount);
 
@@ -222,54 +308,56 @@ blueprint! {
             self.continuous_auth
                 .authorize(|| continuous.burn());
 
-            // return from reserve vault, and empty CONTINUOUS bucket
+            // keep the fee, then return from reserve vault, and empty CONTINUOUS bucket
+            self.fees.put(self.reserve.take(fee_amount));
             (self.reserve.take(return_amount), self.continuous.take(0))
         }
 
         pub fn get_price(&self) -> Decimal {
-            // use the generated stubs for calling methods on the Component (kind of like a virtual call aka dynamic dispatch, but it happens via the kernel)
-            let curve: crate::bonding_curve::BondingCurve = self.bonding_curve.clone().into();
-            curve.get_price(
+            self.bonding_curve.get_price(
                 self.reserve.amount(),
                 self.continuous.resource_manager().total_supply(),
             )
         }
 
-        pub fn get_sell_quote(&mut self, continuous_amount: Decimal) -> ProofOf<RESERVE> {
-            // This variant returns a Proof.  Only possible with sell because
-            // for buying, we can't return "proof" because the amount isn't minted yet
-
-            // use the generated stubs for calling methods on the Component (kind of like a virtual call aka dynamic dispatch, but it happens via the kernel)
-            let curve: crate::bonding_curve::BondingCurve = self.bonding_curve.clone().into();
-            // calculate the amount that would be returned
-            let return_amount = curve.get_return_amount(
-                continuous_amount,
-                self.reserve.amount(),
-                self.continuous.resource_manager().total_supply(),
-            );
-            // return a placeholder for the amount as proof we have it in reserve
-            let bucket = self.reserve.take(return_amount);
-            bucket.create_proof() // return proof of amount, but don't give it away
-        }
-
         pub fn get_buy_quote_amount(&self, collateral_amount: Decimal) -> Decimal {
-            // use the generated stubs for calling methods on the Component (kind of like a virtual call aka dynamic dispatch, but it happens via the kernel)
-            let curve: crate::bonding_curve::BondingCurve = self.bonding_curve.clone().into();
+            // the fee is taken from the collateral before it goes into the reserve
+            let net_collateral_amount = collateral_amount - collateral_amount * self.fee_rate;
+
             // calculate amount that would be minted
-            curve.get_mint_amount(
-                collateral_amount,
+            self.bonding_curve.get_mint_amount(
+                net_collateral_amount,
                 self.reserve.amount(),
                 self.continuous.resource_manager().total_supply(),
             )
         }
 
         pub fn get_sell_quote_amount(&self, continuous_amount: Decimal) -> Decimal {
-            // interestingly, trying to reuse self.get_sell_quote to get a BucketRef and then look at the amount leads to failure with dangling buckets no matter what I've tried
-            // instead just calculate the amount directly
-            // use the generated stubs for calling methods on the Component (kind of like a virtual call aka dynamic dispatch, but it happens via the kernel)
-            let curve: crate::bonding_curve::BondingCurve = self.bonding_curve.clone().into();
+            // the fee is taken from the amount returned out of the reserve
+            let gross_amount = self.get_gross_return_amount(continuous_amount);
+            gross_amount - gross_amount * self.fee_rate
+        }
+
+        pub fn get_fee_rate(&self) -> Decimal {
+            self.fee_rate
+        }
+
+        pub fn get_fees_amount(&self) -> Decimal {
+            self.fees.amount()
+        }
+
+        pub fn set_fee_rate(&mut self, fee_rate: Decimal) {
+            assert_fee_rate(fee_rate);
+            self.fee_rate = fee_rate;
+        }
+
+        pub fn withdraw_fees(&mut self) -> BucketOf<RESERVE> {
+            self.fees.take(self.fees.amount())
+        }
+
+        fn get_gross_return_amount(&self, continuous_amount: Decimal) -> Decimal {
             // calculate the amount that would be returned
-            curve.get_return_amount(
+            self.bonding_curve.get_return_amount(
                 continuous_amount,
                 self.reserve.amount(),
                 self.continuous.resource_manager().total_supply(),
@@ -277,3 +365,10 @@ blueprint! {
         }
     }
 }
+
+fn assert_fee_rate(fee_rate: Decimal) {
+    assert!(
+        !fee_rate.is_negative() && fee_rate < Decimal::one(),
+        "fee rate must be at least 0 and less than 1"
+    );
+}
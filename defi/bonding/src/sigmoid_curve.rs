@@ -0,0 +1,116 @@
+use scrypto::prelude::*;
+
+use bonding_math::integral::IntegralCurve;
+use bonding_math::sigmoid::SigmoidCurve;
+
+use crate::number::*;
+
+blueprint! {
+    struct SigmoidBondingCurve {
+        cap: Decimal,
+        midpoint: Decimal,
+        spread: Decimal,
+        precision_bits: u16,
+    }
+
+    impl SigmoidBondingCurve {
+        pub fn new(
+            cap: Decimal,
+            midpoint: Decimal,
+            spread: Decimal,
+            precision_bits: u16,
+        ) -> ComponentAddress {
+            debug!(
+                "SigmoidBondingCurve::new called with cap {} midpoint {} spread {} @ {} bits",
+                cap, midpoint, spread, precision_bits
+            );
+            // validate the parameters
+            SigmoidCurve::new(
+                amount_from_decimal(cap),
+                amount_from_decimal(midpoint),
+                amount_from_decimal(spread),
+                precision_bits,
+            );
+            Self {
+                cap,
+                midpoint,
+                spread,
+                precision_bits,
+            }
+            .instantiate()
+            .globalize()
+        }
+
+        // can't do trait impl's within the blueprint.  Would be ncie to get those compile time checks...
+        // impl BondingCurve for blueprint::SigmoidBondingCurve {
+
+        pub fn get_initial_supply(&self, collateral_amount: Decimal) -> Decimal {
+            decimal_from_amount(
+                self.curve()
+                    .get_initial_supply(amount_from_decimal(collateral_amount)),
+            )
+        }
+
+        pub fn get_mint_amount(
+            &self,
+            collateral_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            decimal_from_amount(self.curve().get_mint_amount(
+                amount_from_decimal(collateral_amount),
+                amount_from_decimal(reserve_amount),
+                amount_from_decimal(supply_amount),
+            ))
+        }
+
+        pub fn get_return_amount(
+            &self,
+            continuous_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            decimal_from_amount(self.curve().get_return_amount(
+                amount_from_decimal(continuous_amount),
+                amount_from_decimal(reserve_amount),
+                amount_from_decimal(supply_amount),
+            ))
+        }
+
+        pub fn get_price(&self, reserve_amount: Decimal, supply_amount: Decimal) -> Decimal {
+            decimal_from_amount(self.curve().get_price(
+                amount_from_decimal(reserve_amount),
+                amount_from_decimal(supply_amount),
+            ))
+        }
+
+        // }
+
+        pub fn get_sale_quote(
+            &self,
+            continuous_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            self.get_return_amount(continuous_amount, reserve_amount, supply_amount)
+        }
+
+        pub fn get_buy_quote(
+            &self,
+            collateral_amount: Decimal,
+            reserve_amount: Decimal,
+            supply_amount: Decimal,
+        ) -> Decimal {
+            self.get_mint_amount(collateral_amount, reserve_amount, supply_amount)
+        }
+
+        fn curve(&self) -> SigmoidCurve {
+            SigmoidCurve::new(
+                amount_from_decimal(self.cap),
+                amount_from_decimal(self.midpoint),
+                amount_from_decimal(self.spread),
+                self.precision_bits,
+            )
+        }
+    }
+}
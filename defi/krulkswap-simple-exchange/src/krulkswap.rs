@@ -3,6 +3,9 @@ use std::collections::HashMap;
 use crate::util::*;
 use crate::pool::*;
 
+// Maximum number of pools a swap can be routed through.
+const MAX_HOPS: usize = 3;
+
 // Main blueprint
 blueprint! {
     struct Exchange {
@@ -10,34 +13,53 @@ blueprint! {
         // that are created in the exchange by their 2 tokens' addresses.
         pools: HashMap<(ResourceAddress, ResourceAddress), PoolComponent>,
         // Another hashmap stores the PoolComponent instances by their LP token's resource address.
-        pools_lp: HashMap<ResourceAddress, PoolComponent>
+        pools_lp: HashMap<ResourceAddress, PoolComponent>,
+        // Share of the swap fees that goes to the protocol in every pool. 0 means the protocol fee is off.
+        protocol_fee_share: Decimal
     }
 
     impl Exchange {
         // Instantiate a krulkswap exchange.
-        pub fn new_exchange() -> ComponentAddress {
-            Self {
+        // Returns the exchange and an admin badge that can switch on the protocol fee and withdraw it.
+        pub fn new_exchange() -> (ComponentAddress, Bucket) {
+            // Mint the exchange's admin badge.
+            let admin_badge = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "KrulkSwap admin badge")
+                .initial_supply(1);
+
+            let mut exchange = Self {
                 pools: HashMap::new(),
-                pools_lp: HashMap::new()
+                pools_lp: HashMap::new(),
+                protocol_fee_share: Decimal::zero()
             }
-            .instantiate()
-            .globalize()
+            .instantiate();
+
+            // Only the admin can change and withdraw the protocol fee.
+            let access_rules = AccessRules::new()
+                .method("set_protocol_fee_share", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("withdraw_protocol_fees", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .default(AccessRule::AllowAll, AccessRule::DenyAll);
+            exchange.add_access_check(access_rules);
+
+            (exchange.globalize(), admin_badge)
         }
 
         // Adds a new pool to the exchange by instantiating a new PoolComponent.
+        // The LP fee rate is charged on the input tokens of every swap in the pool, e.g. 0.003 for 0.3%.
         // Returns a Bucket of LP tokens that represents the pool.
-        pub fn add_pool(&mut self, mut a: Bucket, mut b: Bucket) -> Bucket {
+        pub fn add_pool(&mut self, mut a: Bucket, mut b: Bucket, lp_fee_rate: Decimal) -> Bucket {
             // Sort the two buckets, so that the key for a tokenpair will always be the same.
             (a, b) = bucket_sort(a, b);
             // Check if the pool already exists.
             assert!(
-                self.pool_exists(a.resource_address(), b.resource_address()),
+                !self.pool_exists(a.resource_address(), b.resource_address()),
                 "This pool already exists"
             );
             // Create the key for the pool by combining the resource addresses.
             let pool_key = (a.resource_address(), b.resource_address());
             // Instantiate a new PoolComponent.
-            let (pool, lp_tokens) = PoolComponent::new_pool(a, b);
+            let (pool, lp_tokens) = PoolComponent::new_pool(a, b, lp_fee_rate, self.protocol_fee_share);
             // Insert the PoolComponent into the hashmap with the pool key.
             self.pools.insert(pool_key, pool);
             lp_tokens
@@ -74,21 +96,127 @@ blueprint! {
         }
 
         // Swap a bucket of token A into a bucket of the target resource.
+        // The swap is routed through intermediate pools when no direct pool exists
+        // or when a route through other pools gives more target tokens.
+        // Fails if the output is less than min_output.
         // Returns one bucket of the target token.
-        pub fn swap(&self, input: Bucket, target_resource: ResourceAddress) -> Bucket {
+        pub fn swap(&self, input: Bucket, target_resource: ResourceAddress, min_output: Decimal) -> Bucket {
+            // Find the best route for the input tokens.
+            let (path, _) = self.quote(input.resource_address(), input.amount(), target_resource);
+            // Pass the tokens through the PoolComponent of every hop on the path.
+            let mut tokens = input;
+            for hop in path.windows(2) {
+                tokens = self.get_pool(hop[0], hop[1]).swap(tokens, hop[1]);
+            }
+            // Check the output, this reverts all swaps on the route if it's too low.
+            assert!(
+                tokens.amount() >= min_output,
+                "The output is less than the minimum output"
+            );
+            tokens
+        }
+
+        // Switch the protocol fee on by setting its share of the swap fees in all pools,
+        // or off by setting it to 0. Only callable with the admin badge.
+        pub fn set_protocol_fee_share(&mut self, protocol_fee_share: Decimal) {
+            assert_fee_share(protocol_fee_share);
+            self.protocol_fee_share = protocol_fee_share;
+            for pool in self.pools.values() {
+                pool.set_protocol_fee_share(protocol_fee_share);
+            }
+        }
+
+        // Withdraw the protocol fees accumulated in all pools. Only callable with the admin badge.
+        // Returns one bucket per token per pool.
+        pub fn withdraw_protocol_fees(&self) -> Vec<Bucket> {
+            self.pools
+                .values()
+                .flat_map(|pool| pool.withdraw_protocol_fees())
+                .collect()
+        }
+
+        // Get the fee rates and the cumulative fees earned in the pool of a token pair.
+        pub fn get_pool_fees(&self, a: ResourceAddress, b: ResourceAddress) -> PoolFees {
             // Check if the pool even exists for this token pair.
             assert!(
-                !self.pool_exists(input.resource_address(), target_resource),
+                self.pool_exists(a, b),
                 "This pool doesn't exist yet"
             );
-            // Create the pool key from the resource address of
-            // the input bucket and the target resource address.
-            let pool_key = (input.resource_address(), target_resource);
-            // Get the PoolComponent from the pool key.
-            let pool = self.pools.get(&pool_key).unwrap();
-            // Pass the buckets off to the PoolComponent's swap
-            // method and return the tokens that come back.
-            pool.swap(input, target_resource)
+            self.get_pool(a, b).fees()
+        }
+
+        // Find the route giving the most target tokens for an amount of input tokens.
+        // Returns the path of resource addresses, starting with the input resource
+        // and ending with the target resource, and the expected output.
+        pub fn quote(
+            &self,
+            input_resource: ResourceAddress,
+            input_amount: Decimal,
+            target_resource: ResourceAddress
+        ) -> (Vec<ResourceAddress>, Decimal) {
+            assert!(input_resource != target_resource, "Cannot swap a token for itself");
+            // Start the search with a path only containing the input resource.
+            let mut path = vec![input_resource];
+            let mut best_route = None;
+            self.search_routes(&mut path, input_amount, target_resource, &mut best_route);
+            // Check if any route exists for this token pair.
+            assert!(best_route.is_some(), "No route exists for this token pair");
+            best_route.unwrap()
+        }
+
+        // Depth first search over all paths of at most MAX_HOPS pools that don't visit a token twice.
+        // Keeps the path with the highest output in best_route.
+        fn search_routes(
+            &self,
+            path: &mut Vec<ResourceAddress>,
+            amount: Decimal,
+            target_resource: ResourceAddress,
+            best_route: &mut Option<(Vec<ResourceAddress>, Decimal)>
+        ) {
+            let current = *path.last().unwrap();
+            for next in self.neighbours(current) {
+                if path.contains(&next) {
+                    continue;
+                }
+                // Ask the pool how many tokens this hop would give.
+                let output = self.get_pool(current, next).quote(current, amount, next);
+                path.push(next);
+                if next == target_resource {
+                    let is_better = match best_route {
+                        Some((_, best_output)) => output > *best_output,
+                        None => true
+                    };
+                    if is_better {
+                        *best_route = Some((path.clone(), output));
+                    }
+                } else if path.len() <= MAX_HOPS {
+                    self.search_routes(path, output, target_resource, best_route);
+                }
+                path.pop();
+            }
+        }
+
+        // Get all tokens that have a pool with the given token.
+        fn neighbours(&self, resource: ResourceAddress) -> Vec<ResourceAddress> {
+            self.pools
+                .keys()
+                .filter_map(|(a, b)| {
+                    if *a == resource {
+                        Some(*b)
+                    } else if *b == resource {
+                        Some(*a)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        }
+
+        // Get the PoolComponent of a token pair.
+        fn get_pool(&self, a: ResourceAddress, b: ResourceAddress) -> &PoolComponent {
+            // Sort the two addresses, so that the key for a tokenpair will always be the same.
+            let (a, b) = address_sort(a, b);
+            self.pools.get(&(a, b)).unwrap()
         }
 
         // Check if a pool exists for a token pair.
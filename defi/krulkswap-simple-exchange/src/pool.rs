@@ -15,7 +15,17 @@ blueprint! {
         // Current total supply of the LP tokens.
         lp_total_supply: Decimal,
         // Special sauce K constant of CFMM. (explained below)
-        k_constant: Decimal
+        k_constant: Decimal,
+        // Fee rate charged on the input tokens of every swap, e.g. 0.003 for 0.3%.
+        lp_fee_rate: Decimal,
+        // Share of the swap fee that goes to the protocol instead of the LPs. 0 means the protocol fee is off.
+        protocol_fee_share: Decimal,
+        // Hashmap stores the vaults where the protocol fees accumulate.
+        protocol_fee_vaults: HashMap<ResourceAddress, Vault>,
+        // Cumulative fees per token earned by the LPs of this pool.
+        lp_fees_earned: HashMap<ResourceAddress, Decimal>,
+        // Cumulative fees per token earned by the protocol in this pool.
+        protocol_fees_earned: HashMap<ResourceAddress, Decimal>
 
         // Many CFMMs (Constant Function Market Makers) are based on the
         // Constant Product. We can define this with the equation:
@@ -33,7 +43,9 @@ blueprint! {
     impl Pool {
         // Instantiate a PoolComponent
         // Returns the instance and a bucket of LP-tokens.
-        pub fn new_pool(a: Bucket, b: Bucket) -> (PoolComponent, Bucket) {
+        pub fn new_pool(a: Bucket, b: Bucket, lp_fee_rate: Decimal, protocol_fee_share: Decimal) -> (PoolComponent, Bucket) {
+            assert_fee_rate(lp_fee_rate);
+            assert_fee_share(protocol_fee_share);
             let a_address = a.resource_address();
             let b_address = b.resource_address();
             // Mint the pool's LP minting badge.
@@ -61,6 +73,16 @@ blueprint! {
             vaults.insert(a_address, Vault::with_bucket(a));
             vaults.insert(b_address, Vault::with_bucket(b));
 
+            // Create empty protocol fee vaults and fee counters for both tokens.
+            let mut protocol_fee_vaults = HashMap::new();
+            let mut lp_fees_earned = HashMap::new();
+            let mut protocol_fees_earned = HashMap::new();
+            for address in [a_address, b_address] {
+                protocol_fee_vaults.insert(address, Vault::new(address));
+                lp_fees_earned.insert(address, Decimal::zero());
+                protocol_fees_earned.insert(address, Decimal::zero());
+            }
+
             // Create the instance.
             (Self {
                 vaults: vaults,
@@ -68,7 +90,12 @@ blueprint! {
                 lp_token: lp_def,
                 lp_initial_supply: k,
                 lp_total_supply: lp_total_supply,
-                k_constant: k
+                k_constant: k,
+                lp_fee_rate: lp_fee_rate,
+                protocol_fee_share: protocol_fee_share,
+                protocol_fee_vaults: protocol_fee_vaults,
+                lp_fees_earned: lp_fees_earned,
+                protocol_fees_earned: protocol_fees_earned
             }
             // Instantiate the instance. and return the instance together with the LP-tokens.
             .instantiate(), lp_tokens)
@@ -134,25 +161,75 @@ blueprint! {
 
         // Swap one token for the target token.
         // Returns a bucket with the target tokens.
-        pub fn swap(&mut self, input: Bucket, target_resource: ResourceAddress) -> Bucket {
+        pub fn swap(&mut self, mut input: Bucket, target_resource: ResourceAddress) -> Bucket {
+            let input_resource = input.resource_address();
+            // Calculate the amount of target tokens to give for the input tokens.
+            let amount_to_give = self.quote(input_resource, input.amount(), target_resource);
+
+            // Split the fee between the LPs and the protocol.
+            let fee = input.amount() * self.lp_fee_rate;
+            let protocol_fee = fee * self.protocol_fee_share;
+            // The protocol fee goes to its own vault, the LP fee stays in the pool with the rest of the input.
+            self.protocol_fee_vaults.get_mut(&input_resource).unwrap().put(input.take(protocol_fee));
+            *self.lp_fees_earned.get_mut(&input_resource).unwrap() += fee - protocol_fee;
+            *self.protocol_fees_earned.get_mut(&input_resource).unwrap() += protocol_fee;
+
+            // Put the input tokens in the pool and give the target tokens.
+            self.put(input);
+            let output = self.take(target_resource, amount_to_give);
+
+            // The LP fee grows the pool, so update the k-value of the pool.
+            let addresses = self.addresses();
+            self.k_constant = self.vaults[&addresses[0]].amount() * self.vaults[&addresses[1]].amount();
+            output
+        }
+
+        // Calculate how many target tokens a swap of some input tokens would give, without swapping.
+        pub fn quote(&self, input_resource: ResourceAddress, input_amount: Decimal, target_resource: ResourceAddress) -> Decimal {
             // Get the balances of the vaults.
-            let input_vault_balance = self.vaults[&input.resource_address()].amount();
+            let input_vault_balance = self.vaults[&input_resource].amount();
             let output_vault_balance = self.vaults[&target_resource].amount();
+            // Only the input tokens after the fee are swapped.
+            let input_after_fee = input_amount * (Decimal::one() - self.lp_fee_rate);
             // Calculate the new balance that A will have.
-            let new_input_amount = input_vault_balance + input.amount();
+            let new_input_amount = input_vault_balance + input_after_fee;
             // Calculate the new balance token B should have.
             // Note that we use a form of the k = a * b equation here: b = k / a
-            let new_output_vault_balance = self.k_constant / new_input_amount;
+            let new_output_vault_balance = input_vault_balance * output_vault_balance / new_input_amount;
             // To get the amount of B to give, we substract the balance of B from the old
             // balance of B, which gives us the difference.
-            let amount_to_give = output_vault_balance - new_output_vault_balance;
-            self.take(target_resource, amount_to_give)
+            output_vault_balance - new_output_vault_balance
+        }
+
+        // Switch the protocol fee on by setting its share of the swap fee, or off by setting it to 0.
+        pub fn set_protocol_fee_share(&mut self, protocol_fee_share: Decimal) {
+            assert_fee_share(protocol_fee_share);
+            self.protocol_fee_share = protocol_fee_share;
+        }
+
+        // Take all accumulated protocol fees out of the pool.
+        // Returns one bucket per token in the pool.
+        pub fn withdraw_protocol_fees(&mut self) -> Vec<Bucket> {
+            self.protocol_fee_vaults
+                .values_mut()
+                .map(|vault| vault.take_all())
+                .collect()
+        }
+
+        // Get the fee rates and the cumulative fees earned in this pool.
+        pub fn fees(&self) -> PoolFees {
+            PoolFees {
+                lp_fee_rate: self.lp_fee_rate,
+                protocol_fee_share: self.protocol_fee_share,
+                lp_fees_earned: self.lp_fees_earned.clone(),
+                protocol_fees_earned: self.protocol_fees_earned.clone()
+            }
         }
 
         // Put some tokens into the pool.
         fn put(&mut self, bucket: Bucket) {
             assert!(
-                !self.vaults.contains_key(&bucket.resource_address()),
+                self.vaults.contains_key(&bucket.resource_address()),
                 "This asset is not in this pool."
             );
             self.vaults.get_mut(&bucket.resource_address()).unwrap().put(bucket);
@@ -161,7 +238,7 @@ blueprint! {
         // Take some tokens out of the pool.
         fn take(&mut self, resource: ResourceAddress, amount: Decimal) -> Bucket {
             assert!(
-                !self.vaults.contains_key(&resource),
+                self.vaults.contains_key(&resource),
                 "This asset is not in this pool."
             );
             self.vaults.get_mut(&resource).unwrap().take(amount)
@@ -170,4 +247,33 @@ blueprint! {
             self.vaults.keys().cloned().collect::<Vec<ResourceAddress>>()
         }
     }
-}
\ No newline at end of file
+}
+
+// Fee rates and cumulative fees of a pool.
+#[derive(Debug, Clone, TypeId, Encode, Decode, Describe)]
+pub struct PoolFees {
+    // Fee rate charged on the input tokens of every swap.
+    pub lp_fee_rate: Decimal,
+    // Share of the swap fee that goes to the protocol.
+    pub protocol_fee_share: Decimal,
+    // Cumulative fees per token earned by the LPs.
+    pub lp_fees_earned: HashMap<ResourceAddress, Decimal>,
+    // Cumulative fees per token earned by the protocol.
+    pub protocol_fees_earned: HashMap<ResourceAddress, Decimal>
+}
+
+// Check that a fee rate is between 0 and 100%, excluding 100%.
+pub fn assert_fee_rate(fee_rate: Decimal) {
+    assert!(
+        fee_rate >= Decimal::zero() && fee_rate < Decimal::one(),
+        "The fee rate must be at least 0 and less than 1"
+    );
+}
+
+// Check that a fee share is between 0 and 100%.
+pub fn assert_fee_share(fee_share: Decimal) {
+    assert!(
+        fee_share >= Decimal::zero() && fee_share <= Decimal::one(),
+        "The fee share must be between 0 and 1"
+    );
+}
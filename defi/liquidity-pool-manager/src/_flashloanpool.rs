@@ -1,5 +1,11 @@
 use scrypto::prelude::*;
 
+/// Epochs are roughly five minutes long, this is only used to annualize the fee APR
+pub const EPOCHS_PER_YEAR: u64 = 105_120;
+
+/// Longest window the fee APR can be computed over, about a week.  Older fees are dropped from the history.
+pub const MAX_APR_EPOCHS: u64 = 2_016;
+
 #[derive(NonFungibleData)]
 pub struct LoanDue {
     pub amount_due: Decimal,
@@ -13,15 +19,29 @@ blueprint! {
         loan_vault: Vault,
         auth_vault: Vault,
         transient_resource_address: ResourceAddress,
+        // Fees are kept in the loan vault, so they raise the value of every LP token as soon as a loan is repaid
+        fees_per_epoch: Vec<(u64, Decimal)>,
+        outstanding_loans: Decimal,
+        outstanding_fees: Decimal,
+        // Largest share of the pool that can be lent at once, across all loans of a transaction
+        max_loan_ratio: Decimal,
+        // Most that can be lent over a single epoch, if any
+        epoch_borrow_cap: Option<Decimal>,
+        borrowed_in_epoch: (u64, Decimal),
+        // (share of the pool, fee rate) pairs sorted by share.  A loan pays the rate of the highest tier its
+        // share reaches, or loan_fee_rate below the first one.
+        fee_tiers: Vec<(Decimal, Decimal)>,
     }
 
     impl FlashLoanPool {
         /// The most elementary possible flash loan.  Creates a loan pool from whatever is initially supplied,
         /// provides loans with a .1% fee, and lets anyone freely add liquidity.
         ///
-        /// Does NOT reward liquidity providers in any way or provide a way to remove liquidity from the pool.
-        /// Minting LP tokens for rewards, and removing liquidity, is covered in other examples, such as:
-        /// https://github.com/radixdlt/scrypto-examples/tree/main/defi/radiswap
+        /// The admin can cap the share of the pool lent at once and the volume lent per epoch, and make larger loans
+        /// pay higher fees with set_fee_tiers, so no single borrower can drain the pool cheaply.
+        ///
+        /// Fees stay in the loan vault, so whoever holds a share of the pool (see PoolManager and FlashLoanService)
+        /// earns them as soon as the loan is repaid.
         pub fn instantiate_default(pool_resource_addresse: ResourceAddress,loan_fee_rate:Decimal) -> (ComponentAddress,Bucket) {
 
             assert!(
@@ -58,12 +78,23 @@ blueprint! {
                 auth_vault: Vault::with_bucket(auth_token),
                 transient_resource_address: address,
                 loan_fee_rate,
+                fees_per_epoch: Vec::new(),
+                outstanding_loans: Decimal::zero(),
+                outstanding_fees: Decimal::zero(),
+                max_loan_ratio: Decimal::one(),
+                epoch_borrow_cap: None,
+                borrowed_in_epoch: (Runtime::current_epoch(), Decimal::zero()),
+                fee_tiers: Vec::new(),
             }
             .instantiate();
 
             let access_rules = AccessRules::new()
                 .method("add_liquidity", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
                 .method("remove_liquidity", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_loan_fee_rate", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_max_loan_ratio", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_epoch_borrow_cap", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_fee_tiers", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
                 .default(AccessRule::AllowAll, AccessRule::DenyAll);
 
             flash_loan_component.add_access_check(access_rules);
@@ -77,22 +108,149 @@ blueprint! {
         }
 
         pub fn add_liquidity(&mut self, tokens: Bucket) {
+            // The pool size is what LP tokens are priced against, it must not be read while a loan is out
+            assert!(
+                self.outstanding_loans == Decimal::zero(),
+                "Liquidity can't change while a loan is outstanding"
+            );
             self.loan_vault.put(tokens);
         }
 
         pub fn remove_liquidity(&mut self, tokens: Decimal) -> Bucket{
+            assert!(
+                self.outstanding_loans == Decimal::zero(),
+                "Liquidity can't change while a loan is outstanding"
+            );
             self.loan_vault.take(tokens)
         }
 
+        /// Fees collected from repaid loans over the last `epochs` epochs, including the current one
+        pub fn collected_fees(&self, epochs: u64) -> Decimal {
+            assert!(
+                epochs <= MAX_APR_EPOCHS,
+                "Fees are only kept for the last {} epochs",
+                MAX_APR_EPOCHS
+            );
+            let since = Runtime::current_epoch().saturating_sub(epochs.saturating_sub(1));
+            self.fees_per_epoch
+                .iter()
+                .rev()
+                .take_while(|(epoch, _)| *epoch >= since)
+                .fold(Decimal::zero(), |total, (_, fees)| total + *fees)
+        }
+
+        /// Annualized return of the fees collected over the last `epochs` epochs, relative to the liquidity
+        /// that earned them.  Eg. 0.05 is a 5% APR.
+        pub fn fee_apr(&self, epochs: u64) -> Decimal {
+            assert!(epochs > 0, "At least one epoch is needed");
+
+            let fees = self.collected_fees(epochs);
+            let principal = self.loan_vault.amount() - fees;
+            if principal <= Decimal::zero() {
+                return Decimal::zero();
+            }
+            fees / principal * Decimal::from(EPOCHS_PER_YEAR) / Decimal::from(epochs)
+        }
+
+        /// Fee rate a loan of `loan_amount` would pay right now.  The tier is picked from everything borrowed in the
+        /// transaction so far plus this loan, as a share of the liquidity available before the first of them.
+        /// take_loan charges the whole tier on that total, so splitting a large loan into smaller ones doesn't lower
+        /// its fee.
+        pub fn loan_fee_rate_for(&self, loan_amount: Decimal) -> Decimal {
+            let pool_amount = self.pool_amount();
+            if pool_amount == Decimal::zero() {
+                return self.loan_fee_rate;
+            }
+            // Liquidity can't change while loans are out and they all have to be repaid within the transaction, so
+            // the outstanding loans are exactly what was borrowed earlier in this transaction
+            let share = (self.outstanding_loans + loan_amount) / pool_amount;
+            self.fee_tiers
+                .iter()
+                .rev()
+                .find(|(min_share, _)| share >= *min_share)
+                .map(|(_, fee_rate)| *fee_rate)
+                .unwrap_or(self.loan_fee_rate)
+        }
+
+        /// How much can still be borrowed in this transaction, given the loan size and epoch limits
+        pub fn max_loan_amount(&self) -> Decimal {
+            let mut max_amount = std::cmp::min(
+                self.pool_amount() * self.max_loan_ratio - self.outstanding_loans,
+                self.loan_vault.amount(),
+            );
+            if let Some(cap) = self.epoch_borrow_cap {
+                max_amount = std::cmp::min(max_amount, cap - self.borrowed_this_epoch());
+            }
+            std::cmp::max(max_amount, Decimal::zero())
+        }
+
+        pub fn set_loan_fee_rate(&mut self, loan_fee_rate: Decimal) {
+            assert!(
+                loan_fee_rate > dec!(0),
+                "Loan interest can't be negative"
+            );
+            if let Some((_, first_tier_rate)) = self.fee_tiers.first() {
+                assert!(loan_fee_rate < *first_tier_rate, "Fee tiers have to rise above the base fee rate");
+            }
+            self.loan_fee_rate = loan_fee_rate;
+        }
+
+        /// Limits the loans of a transaction to this share of the pool, eg. 0.5 for half of it
+        pub fn set_max_loan_ratio(&mut self, max_loan_ratio: Decimal) {
+            assert!(
+                max_loan_ratio > Decimal::zero() && max_loan_ratio <= Decimal::one(),
+                "The max loan ratio has to be above 0 and at most 1"
+            );
+            self.max_loan_ratio = max_loan_ratio;
+        }
+
+        /// Limits the total amount lent over an epoch, None to lift the limit
+        pub fn set_epoch_borrow_cap(&mut self, epoch_borrow_cap: Option<Decimal>) {
+            if let Some(cap) = epoch_borrow_cap {
+                assert!(cap > Decimal::zero(), "The epoch borrow cap has to be positive");
+            }
+            self.epoch_borrow_cap = epoch_borrow_cap;
+        }
+
+        /// Replaces the fee tiers with (share of the pool, fee rate) pairs.  Both have to be strictly increasing, with
+        /// every rate above loan_fee_rate.  An empty list charges loan_fee_rate on every loan.
+        pub fn set_fee_tiers(&mut self, fee_tiers: Vec<(Decimal, Decimal)>) {
+            let mut previous = (Decimal::zero(), self.loan_fee_rate);
+            for (min_share, fee_rate) in fee_tiers.iter() {
+                assert!(
+                    *min_share > previous.0 && *min_share <= Decimal::one(),
+                    "Fee tier shares have to be increasing, between 0 and 1"
+                );
+                assert!(*fee_rate > previous.1, "Fee tier rates have to be increasing");
+                previous = (*min_share, *fee_rate);
+            }
+            self.fee_tiers = fee_tiers;
+        }
+
         pub fn take_loan(&mut self, loan_amount: Decimal) -> (Bucket, Bucket) {
             assert!(
                 loan_amount <= self.loan_vault.amount(),
                 "Not enough liquidity to supply this loan!"
             );
+            assert!(
+                self.outstanding_loans + loan_amount <= self.pool_amount() * self.max_loan_ratio,
+                "Loan exceeds the maximum share of the pool"
+            );
+            if let Some(cap) = self.epoch_borrow_cap {
+                assert!(
+                    self.borrowed_this_epoch() + loan_amount <= cap,
+                    "Loan exceeds what can still be borrowed this epoch"
+                );
+            }
 
             // Calculate how much we must be repaid
 
-            let fees = loan_amount * (self.loan_fee_rate);
+            // The loans of the transaction pay the tier of their total, the earlier ones paid for their part already
+            let fees = (self.outstanding_loans + loan_amount) * self.loan_fee_rate_for(loan_amount)
+                - self.outstanding_fees;
+
+            let epoch = Runtime::current_epoch();
+            self.borrowed_in_epoch = (epoch, self.borrowed_this_epoch() + loan_amount);
 
             // Mint an NFT with the loan terms.  Remember that this resource previously had rules defined which
             // forbid it from ever being deposited in any vault.  Thus, once it is present in the transaction
@@ -111,6 +269,8 @@ blueprint! {
                     },
                 )
             });
+            self.outstanding_loans += loan_amount;
+            self.outstanding_fees += fees;
             (self.loan_vault.take(loan_amount), loan_terms)
         }
 
@@ -130,6 +290,9 @@ blueprint! {
             // We could also verify that the resource being repaid is of the correct kind, and give a friendly
             // error message if not. For this example we'll just let the engine handle that when we try to deposit
             self.loan_vault.put(loan_repayment.take( terms.amount_due));
+            self.outstanding_loans -= terms.loan_amount;
+            self.outstanding_fees -= terms.fees;
+            self.record_fees(terms.fees);
 
             // We have our payment; we can now burn the transient token
             self.auth_vault.authorize(|| loan_terms.burn());
@@ -138,5 +301,32 @@ blueprint! {
             // Return the change to the work top
             loan_repayment
         }
+
+        // Liquidity owned by the pool, including what is currently lent out
+        fn pool_amount(&self) -> Decimal {
+            self.loan_vault.amount() + self.outstanding_loans
+        }
+
+        fn borrowed_this_epoch(&self) -> Decimal {
+            let (epoch, borrowed) = self.borrowed_in_epoch;
+            if epoch == Runtime::current_epoch() {
+                borrowed
+            } else {
+                Decimal::zero()
+            }
+        }
+
+        fn record_fees(&mut self, fees: Decimal) {
+            let epoch = Runtime::current_epoch();
+            match self.fees_per_epoch.last_mut() {
+                Some((last_epoch, total)) if *last_epoch == epoch => *total += fees,
+                _ => self.fees_per_epoch.push((epoch, fees)),
+            }
+
+            // Keep only what the longest APR window can use
+            let oldest = epoch.saturating_sub(MAX_APR_EPOCHS - 1);
+            let expired = self.fees_per_epoch.iter().take_while(|(epoch, _)| *epoch < oldest).count();
+            self.fees_per_epoch.drain(..expired);
+        }
     }
 }
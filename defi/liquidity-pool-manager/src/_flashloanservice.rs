@@ -14,6 +14,12 @@ external_component! {
         fn add_liquidity(&mut self, tokens: Bucket) ;
         fn remove_liquidity(&mut self, tokens: Decimal) -> Bucket;
         fn available_liquidity(&self) -> Decimal;
+        fn collected_fees(&self, epochs: u64) -> Decimal;
+        fn fee_apr(&self, epochs: u64) -> Decimal;
+        fn set_loan_fee_rate(&mut self, loan_fee_rate: Decimal);
+        fn set_max_loan_ratio(&mut self, max_loan_ratio: Decimal);
+        fn set_epoch_borrow_cap(&mut self, epoch_borrow_cap: Option<Decimal>);
+        fn set_fee_tiers(&mut self, fee_tiers: Vec<(Decimal, Decimal)>);
     }
 }
 
@@ -43,19 +49,36 @@ blueprint! {
 
     impl FlashLoanService {
 
-        pub fn new(flashloanpool_package_address:PackageAddress,poolmanager_package_address:PackageAddress,pool_resource_addresse: ResourceAddress,lp_symbol: String, lp_name: String) -> ComponentAddress{
+        /// Returns the service and its admin badge, which configures the loan limits and fee tiers of the pool
+        pub fn new(flashloanpool_package_address:PackageAddress,poolmanager_package_address:PackageAddress,pool_resource_addresse: ResourceAddress,lp_symbol: String, lp_name: String) -> (ComponentAddress,Bucket){
 
             let (flashloanpool,flashloanpool_admin_badge) =   FlashLoanPoolTarget::at(flashloanpool_package_address,"FlashLoanPool").instantiate_default( pool_resource_addresse,dec!("0.01"));
 
             let (poolmanager,poolmanager_admin_badge) =   PoolManagerTarget::at(poolmanager_package_address,"PoolManager").new(lp_symbol,lp_name);
 
-            Self {
+            let admin_badge = ResourceBuilder::new_fungible()
+            .divisibility(DIVISIBILITY_NONE)
+            .metadata("name", "FlashLoanService admin bage")
+            .initial_supply(1);
+
+            let mut service = Self {
                 flashloanpool,
                 poolmanager,
                 flashloanpool_admin_badge: Vault::with_bucket(flashloanpool_admin_badge),
                 poolmanager_admin_badge: Vault::with_bucket(poolmanager_admin_badge),
 
-            }.instantiate().globalize()
+            }.instantiate();
+
+            let access_rules = AccessRules::new()
+                .method("set_loan_fee_rate", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_max_loan_ratio", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_epoch_borrow_cap", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("set_fee_tiers", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .default(AccessRule::AllowAll, AccessRule::DenyAll);
+
+            service.add_access_check(access_rules);
+
+            (service.globalize(),admin_badge)
 
         }
 
@@ -77,6 +100,8 @@ blueprint! {
             lp_tokens
         }
 
+        /// Burns the LP tokens and returns their share of the pool: the liquidity provided plus the fees
+        /// collected from repaid loans since.
         pub fn remove_liquidity(&self,lp_tokens:Bucket) -> Bucket{
             ComponentAuthZone::push(self.poolmanager_admin_badge.create_proof());
             ComponentAuthZone::push(self.flashloanpool_admin_badge.create_proof());
@@ -94,6 +119,48 @@ blueprint! {
             tokens
         }
 
+        /// Fees collected by the pool over the last `epochs` epochs
+        pub fn get_collected_fees(&self, epochs: u64) -> Decimal {
+            FlashLoanPoolComponentTarget::at(self.flashloanpool).collected_fees(epochs)
+        }
+
+        /// Current APR earned by liquidity providers, extrapolated from the fees of the last `epochs` epochs
+        pub fn get_fee_apr(&self, epochs: u64) -> Decimal {
+            FlashLoanPoolComponentTarget::at(self.flashloanpool).fee_apr(epochs)
+        }
+
+        pub fn set_loan_fee_rate(&self, loan_fee_rate: Decimal) {
+            ComponentAuthZone::push(self.flashloanpool_admin_badge.create_proof());
+
+            FlashLoanPoolComponentTarget::at(self.flashloanpool).set_loan_fee_rate(loan_fee_rate);
+
+            ComponentAuthZone::pop().drop();
+        }
+
+        pub fn set_max_loan_ratio(&self, max_loan_ratio: Decimal) {
+            ComponentAuthZone::push(self.flashloanpool_admin_badge.create_proof());
+
+            FlashLoanPoolComponentTarget::at(self.flashloanpool).set_max_loan_ratio(max_loan_ratio);
+
+            ComponentAuthZone::pop().drop();
+        }
+
+        pub fn set_epoch_borrow_cap(&self, epoch_borrow_cap: Option<Decimal>) {
+            ComponentAuthZone::push(self.flashloanpool_admin_badge.create_proof());
+
+            FlashLoanPoolComponentTarget::at(self.flashloanpool).set_epoch_borrow_cap(epoch_borrow_cap);
+
+            ComponentAuthZone::pop().drop();
+        }
+
+        pub fn set_fee_tiers(&self, fee_tiers: Vec<(Decimal, Decimal)>) {
+            ComponentAuthZone::push(self.flashloanpool_admin_badge.create_proof());
+
+            FlashLoanPoolComponentTarget::at(self.flashloanpool).set_fee_tiers(fee_tiers);
+
+            ComponentAuthZone::pop().drop();
+        }
+
         pub fn simulate_fee(&self,tokens:Bucket) {
 
             ComponentAuthZone::push(self.flashloanpool_admin_badge.create_proof());
@@ -0,0 +1,183 @@
+use scrypto::prelude::*;
+
+#[derive(NonFungibleData)]
+pub struct MultiLoanDue {
+    pub amounts_due: HashMap<ResourceAddress, Decimal>,
+    pub loan_amounts: HashMap<ResourceAddress, Decimal>,
+    pub fees: HashMap<ResourceAddress, Decimal>,
+}
+
+blueprint! {
+    struct MultiFlashLoanPool {
+        loan_fee_rate: Decimal,
+        loan_vaults: HashMap<ResourceAddress, Vault>,
+        auth_vault: Vault,
+        transient_resource_address: ResourceAddress,
+    }
+
+    impl MultiFlashLoanPool {
+        /// Same as FlashLoanPool but lends from one vault per resource, so several resources can be borrowed in a
+        /// single call, eg. for arbitrage across two pools.  All of them are listed on a single transient
+        /// MultiLoanDue NFT and have to be repaid together.
+        pub fn instantiate_default(pool_resource_addresses: Vec<ResourceAddress>, loan_fee_rate: Decimal) -> (ComponentAddress, Bucket) {
+
+            assert!(
+                loan_fee_rate > dec!(0),
+                "Loan interest can't be negative"
+            );
+            assert!(
+                !pool_resource_addresses.is_empty(),
+                "At least one resource is needed to lend"
+            );
+
+            let auth_token = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "Admin authority for MultiFlashLoanPool")
+                .initial_supply(1);
+
+            // Define a "transient" resource which can never be deposited once created, only burned
+            let address = ResourceBuilder::new_non_fungible(NonFungibleIdType::UUID)
+                .metadata(
+                    "name",
+                    "Promise token for MultiFlashLoanPool - must be returned to be burned!",
+                )
+                .mintable(rule!(require(auth_token.resource_address())), AccessRule::DenyAll)
+                .burnable(rule!(require(auth_token.resource_address())), AccessRule::DenyAll)
+                .restrict_deposit(AccessRule::DenyAll, AccessRule::DenyAll)
+                .no_initial_supply();
+
+            //  Lock components methods to admin only
+
+            let admin_badge = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "MultiFlashLoanPool admin badge")
+                .initial_supply(1);
+
+            let mut loan_vaults = HashMap::new();
+            for resource_address in pool_resource_addresses {
+                loan_vaults.insert(resource_address, Vault::new(resource_address));
+            }
+
+            let mut flash_loan_component = Self {
+                loan_vaults,
+                auth_vault: Vault::with_bucket(auth_token),
+                transient_resource_address: address,
+                loan_fee_rate,
+            }
+            .instantiate();
+
+            let access_rules = AccessRules::new()
+                .method("add_liquidity", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .method("remove_liquidity", rule!(require(admin_badge.resource_address())), AccessRule::DenyAll)
+                .default(AccessRule::AllowAll, AccessRule::DenyAll);
+
+            flash_loan_component.add_access_check(access_rules);
+
+            (flash_loan_component.globalize(), admin_badge)
+        }
+
+        pub fn available_liquidity(&self, resource_address: ResourceAddress) -> Decimal {
+            self.loan_vault(resource_address).amount()
+        }
+
+        pub fn add_liquidity(&mut self, tokens: Bucket) {
+            self.loan_vault_mut(tokens.resource_address()).put(tokens);
+        }
+
+        pub fn remove_liquidity(&mut self, resource_address: ResourceAddress, tokens: Decimal) -> Bucket {
+            self.loan_vault_mut(resource_address).take(tokens)
+        }
+
+        /// Lends every requested amount at once.  Returns one bucket per resource and the MultiLoanDue NFT, which can
+        /// only leave the transaction by being burned in repay_loans.
+        pub fn take_loans(&mut self, loan_amounts: HashMap<ResourceAddress, Decimal>) -> (Vec<Bucket>, Bucket) {
+            assert!(!loan_amounts.is_empty(), "No loan requested");
+
+            let mut amounts_due = HashMap::new();
+            let mut fees = HashMap::new();
+            let mut loans = Vec::new();
+            for (resource_address, loan_amount) in loan_amounts.iter() {
+                assert!(*loan_amount > Decimal::zero(), "Loan amount must be positive");
+
+                assert!(
+                    *loan_amount <= self.available_liquidity(*resource_address),
+                    "Not enough liquidity to supply this loan!"
+                );
+
+                // Calculate how much we must be repaid
+                let fee = *loan_amount * self.loan_fee_rate;
+                amounts_due.insert(*resource_address, *loan_amount + fee);
+                fees.insert(*resource_address, fee);
+                loans.push(self.loan_vault_mut(*resource_address).take(*loan_amount));
+            }
+
+            // A single NFT lists the terms of every loan.  Like with FlashLoanPool, it can't be deposited anywhere so the
+            // transaction only completes once all of them are repaid and the NFT is burned.
+            let loan_terms = self.auth_vault.authorize(|| {
+                borrow_resource_manager!(self.transient_resource_address).mint_non_fungible(
+                    &NonFungibleId::random(),
+                    MultiLoanDue {
+                        amounts_due,
+                        loan_amounts,
+                        fees,
+                    },
+                )
+            });
+            (loans, loan_terms)
+        }
+
+        /// Repays all loans listed on the MultiLoanDue NFT, with one bucket per borrowed resource.  Returns the change.
+        pub fn repay_loans(&mut self, loan_repayments: Vec<Bucket>, loan_terms: Bucket) -> Vec<Bucket> {
+            assert!(
+                loan_terms.resource_address() == self.transient_resource_address,
+                "Incorrect resource passed in for loan terms"
+            );
+
+            let terms: MultiLoanDue = loan_terms.non_fungible().data();
+            let mut repaid = HashSet::new();
+            let mut change = Vec::new();
+            for mut loan_repayment in loan_repayments {
+                let resource_address = loan_repayment.resource_address();
+                let amount_due = *terms
+                    .amounts_due
+                    .get(&resource_address)
+                    .expect("Repayment given for a resource that wasn't borrowed");
+                assert!(
+                    repaid.insert(resource_address),
+                    "Repay each resource in a single bucket"
+                );
+
+                // Verify we are being sent at least the amount due
+                assert!(
+                    loan_repayment.amount() >= amount_due,
+                    "Insufficient repayment given for your loan!"
+                );
+                self.loan_vault_mut(resource_address).put(loan_repayment.take(amount_due));
+                change.push(loan_repayment);
+            }
+
+            assert!(
+                repaid.len() == terms.amounts_due.len(),
+                "Every borrowed resource has to be repaid!"
+            );
+
+            // We have our payments; we can now burn the transient token
+            self.auth_vault.authorize(|| loan_terms.burn());
+
+            // Return the change to the work top
+            change
+        }
+
+        fn loan_vault(&self, resource_address: ResourceAddress) -> &Vault {
+            self.loan_vaults
+                .get(&resource_address)
+                .expect("Resource not lent by this pool")
+        }
+
+        fn loan_vault_mut(&mut self, resource_address: ResourceAddress) -> &mut Vault {
+            self.loan_vaults
+                .get_mut(&resource_address)
+                .expect("Resource not lent by this pool")
+        }
+    }
+}
@@ -2,4 +2,5 @@ use scrypto::prelude::*;
 
 mod _flashloanpool;
 mod _flashloanservice;
+mod _multiflashloanpool;
 mod _poolmanager;
@@ -61,12 +61,14 @@ const flashloanpool_admin_badge = output[5]
 const lp_mint_badge = output[6]
 const poolmanager_admin_badge = output[7]
 const lp_token = output[8]
+const service_admin_badge = output[9]
 
 setEnvValue('flashloanpool_component', flashloanpool_component);
 setEnvValue('poolmanager_component', poolmanager_component);
 setEnvValue('service_component', service_component);
 setEnvValue('lp_token', lp_token);
 setEnvValue('transient_token', transient_token);
+setEnvValue('service_admin_badge', service_admin_badge);
 
 // Add liquidity from account1
 await e($`resim set-default-account ${account1} ${privkey1}`)
@@ -82,11 +84,52 @@ await e($`resim call-method ${service_component} add_liquidity 900,${tokenXRD}`)
 // simulate pool first fee collection
 await take_flash_loan(e, 2);
 
+// APR from the fees of the last 10 epochs
+await e($`resim call-method ${service_component} get_fee_apr 10`, false)
+
 // Remove all liquidity in the pool
 await e($`resim set-default-account ${account1} ${privkey1}`)
 await e($`resim call-method ${service_component} remove_liquidity 900,${lp_token}`)
 
+// Cap loans to half of the pool and 1000 per epoch, larger loans paying higher fees.  The pool holds a bit more
+// than 900 at this point.  Start a new epoch so the loans above don't count against the cap.
+await e($`resim set-default-account ${account} ${privkey}`)
+await e($`resim set-current-epoch 1`)
+await e($`resim run ./rtm/configure_loans.rtm`)
+
+// Two loans of 200 in one transaction.  The first is about a fifth of the pool and owes 2%, together they are over
+// a quarter of it so the second owes the 5% of both minus what the first already owes: 400 * 5% - 4
+await e($`resim call-method ${flashloanpool_component} loan_fee_rate_for 400`, false)
+await e($`resim run ./rtm/flash_loan_tiered.rtm`)
+
+// Over half of the pool, rejected
+await expect_failure($`resim run ./rtm/flash_loan_over_ratio.rtm`)
+
+// 800 borrowed in this epoch, anything over 200 more is rejected
+await e($`resim run ./rtm/flash_loan_tiered.rtm`)
+await expect_failure($`resim run ./rtm/flash_loan_over_epoch_cap.rtm`)
+
+// Multi-asset flash loan, borrowing XRD and a second token against a single transient NFT
+await e($`resim set-default-account ${account} ${privkey}`)
+const tokenB = (await e($`resim new-token-fixed --symbol TKB 10000`))[0]
+setEnvValue('tokenB', tokenB);
 
+output = await e($`resim run ./rtm/multi_flash_loan_setup.rtm`)
+setEnvValue('multiflashloanpool_component', output[0]);
+setEnvValue('multi_transient_token', output[2]);
+setEnvValue('multi_admin_badge', output[3]);
+
+await e($`resim run ./rtm/multi_flash_loan_add_liquidity.rtm`)
+await e($`resim run ./rtm/multi_flash_loan.rtm`)
+
+
+
+async function expect_failure(command) {
+    const result = await quiet(command.nothrow())
+    if (result.exitCode === 0) {
+        throw new Error(`Expected the transaction to fail:\n${result.stdout}`)
+    }
+}
 
 async function take_flash_loan(e, count = 30) {
     await e($`resim set-default-account ${account} ${privkey}`);
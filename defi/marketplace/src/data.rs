@@ -55,33 +55,212 @@ pub struct OrderTicket {
   pub order_currency: String
 }
 
+/// A price recorded for an asset at a given epoch
+#[derive(Debug, Clone, sbor::Decode, sbor::Encode, sbor::Describe, sbor::TypeId)]
+pub struct PriceObservation {
+    /// Epoch in which the price was recorded
+    pub epoch: u64,
+    /// Price of the last trade in that epoch
+    pub price: Decimal,
+    /// Sum of `price * epochs` over all epochs before this observation
+    pub cumulative_price: Decimal
+}
+
+/// Longest window, in epochs, a time-weighted average price can be computed over
+pub const MAX_TWAP_WINDOW: u64 = 1000;
+
+/// Price feed of a single asset
+#[derive(Debug, sbor::Decode, sbor::Encode, sbor::Describe, sbor::TypeId)]
+pub struct PriceFeed {
+    /// Ring buffer of the most recent prices (at most one per epoch), enough to cover `MAX_TWAP_WINDOW`
+    history: Vec<PriceObservation>,
+    /// Index of the most recent price in `history`
+    newest: usize
+}
+
+#[allow(dead_code)]
+impl PriceFeed {
+    const CAPACITY: usize = MAX_TWAP_WINDOW as usize + 1;
+
+    pub fn new() -> PriceFeed {
+        PriceFeed { history: vec![], newest: 0 }
+    }
+
+    /// Recorded prices, oldest first
+    pub fn history(&self) -> Vec<PriceObservation> {
+        let (recent, oldest) = self.history.split_at(self.history.len().min(self.newest + 1));
+        oldest.iter().chain(recent.iter()).cloned().collect()
+    }
+
+    pub fn last(&self) -> &PriceObservation {
+        &self.history[self.newest]
+    }
+
+    /// Number of epochs since the last price was recorded
+    pub fn age(&self, epoch: u64) -> u64 {
+        epoch - self.last().epoch
+    }
+
+    /// Cumulative price up to (but not including) the given epoch.
+    /// Returns None if the epoch is before the oldest recorded price.
+    pub fn cumulative_price_at(&self, epoch: u64) -> Option<Decimal> {
+        let (recent, oldest) = self.history.split_at(self.history.len().min(self.newest + 1));
+        recent
+            .iter()
+            .rev()
+            .chain(oldest.iter().rev())
+            .find(|o| o.epoch <= epoch)
+            .map(|o| o.cumulative_price + o.price * Decimal::from(epoch - o.epoch))
+    }
+
+    /// Time-weighted average price over the `window` epochs before the given epoch.
+    /// Returns None if the window starts before the oldest recorded price or is longer than `MAX_TWAP_WINDOW`.
+    pub fn twap(&self, epoch: u64, window: u64) -> Option<Decimal> {
+        if window == 0 {
+            return Some(self.last().price);
+        }
+        if window > MAX_TWAP_WINDOW {
+            return None;
+        }
+
+        let start = epoch.checked_sub(window)?;
+        let start_cumulative = self.cumulative_price_at(start)?;
+        let end_cumulative = self.cumulative_price_at(epoch)?;
+
+        Some((end_cumulative - start_cumulative) / Decimal::from(window))
+    }
+
+    pub fn update(&mut self, epoch: u64, price: Decimal) {
+        if !self.history.is_empty() && self.last().epoch == epoch {
+            self.history[self.newest].price = price;
+            return;
+        }
+
+        let cumulative_price = self.cumulative_price_at(epoch).unwrap_or(0.into());
+        let observation = PriceObservation { epoch, price, cumulative_price };
+
+        if self.history.len() < Self::CAPACITY {
+            self.history.push(observation);
+            self.newest = self.history.len() - 1;
+        } else {
+            // Overwrite the oldest price
+            self.newest = (self.newest + 1) % Self::CAPACITY;
+            self.history[self.newest] = observation;
+        }
+    }
+}
+
+/// On-ledger price feed recording the last trade price of every asset traded on the market
 #[derive(Debug, sbor::Decode, sbor::Encode, sbor::Describe, sbor::TypeId)]
 pub struct MarketPrices {
-    asset_prices: HashMap<String, Decimal>
+    feeds: HashMap<Address, PriceFeed>
 }
 
 #[allow(dead_code)]
 impl MarketPrices {
     pub fn new() -> MarketPrices {
-        MarketPrices { asset_prices: HashMap::new() }
+        MarketPrices { feeds: HashMap::new() }
+    }
+
+    pub fn assets(&self) -> Vec<Address> {
+        self.feeds.keys().cloned().collect()
     }
 
-    pub fn assets(&self) -> Vec<String> {
-        self.asset_prices.keys().cloned().collect()
+    pub fn get(&self, asset: Address) -> Option<Decimal> {
+        self.feeds.get(&asset).map(|feed| feed.last().price)
     }
 
-    pub fn get(&self, asset_symbol: String) -> Option<Decimal> {
-        self.asset_prices.get(&asset_symbol).cloned()
+    /// Number of epochs since the last trade of the given asset
+    pub fn age(&self, asset: Address, epoch: u64) -> Option<u64> {
+        self.feeds.get(&asset).map(|feed| feed.age(epoch))
     }
 
-    pub fn for_address(&self, address: Address) -> Option<Decimal> {
-        let resource_def = ResourceDef::from(address);
-        let asset_symbol = resource_def.metadata()["symbol"].clone();
+    pub fn twap(&self, asset: Address, epoch: u64, window: u64) -> Option<Decimal> {
+        self.feeds.get(&asset).and_then(|feed| feed.twap(epoch, window))
+    }
+
+    pub fn history(&self, asset: Address) -> Vec<PriceObservation> {
+        self.feeds.get(&asset).map(|feed| feed.history()).unwrap_or(vec![])
+    }
 
-        self.get(asset_symbol)
+    pub fn update(&mut self, asset: Address, epoch: u64, price: Decimal) {
+        self.feeds
+            .entry(asset)
+            .or_insert_with(PriceFeed::new)
+            .update(epoch, price);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_keeps_last_price_of_epoch() {
+        let mut feed = PriceFeed::new();
+        feed.update(1, Decimal::from(10));
+        feed.update(1, Decimal::from(12));
+        feed.update(3, Decimal::from(20));
+
+        let history = feed.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].epoch, history[0].price), (1, Decimal::from(12)));
+        assert_eq!((history[1].epoch, history[1].price), (3, Decimal::from(20)));
+        assert_eq!(history[1].cumulative_price, Decimal::from(24));
+        assert_eq!(feed.last().price, Decimal::from(20));
+    }
+
+    #[test]
+    fn test_cumulative_price_at() {
+        let mut feed = PriceFeed::new();
+        feed.update(2, Decimal::from(10));
+        feed.update(5, Decimal::from(20));
+
+        assert_eq!(feed.cumulative_price_at(1), None);
+        assert_eq!(feed.cumulative_price_at(2), Some(Decimal::from(0)));
+        assert_eq!(feed.cumulative_price_at(4), Some(Decimal::from(20)));
+        assert_eq!(feed.cumulative_price_at(5), Some(Decimal::from(30)));
+        assert_eq!(feed.cumulative_price_at(7), Some(Decimal::from(70)));
+    }
+
+    #[test]
+    fn test_twap() {
+        let mut feed = PriceFeed::new();
+        feed.update(0, Decimal::from(10));
+        feed.update(2, Decimal::from(40));
+
+        assert_eq!(feed.twap(4, 0), Some(Decimal::from(40)));
+        assert_eq!(feed.twap(4, 2), Some(Decimal::from(40)));
+        assert_eq!(feed.twap(4, 4), Some(Decimal::from(25)));
+        assert_eq!(feed.twap(4, 5), None);
+        assert_eq!(feed.twap(MAX_TWAP_WINDOW + 4, MAX_TWAP_WINDOW + 1), None);
+    }
+
+    #[test]
+    fn test_age() {
+        let mut feed = PriceFeed::new();
+        feed.update(3, Decimal::from(10));
+
+        assert_eq!(feed.age(3), 0);
+        assert_eq!(feed.age(10), 7);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut feed = PriceFeed::new();
+        let last_epoch = 2 * MAX_TWAP_WINDOW;
+        for epoch in 0..=last_epoch {
+            feed.update(epoch, Decimal::from(epoch));
+        }
+
+        let history = feed.history();
+        assert_eq!(history.len() as u64, MAX_TWAP_WINDOW + 1);
+        assert_eq!(history.first().unwrap().epoch, MAX_TWAP_WINDOW);
+        assert_eq!(history.last().unwrap().epoch, last_epoch);
 
-    pub fn update(&mut self, asset_symbol: String, price: Decimal) {
-        self.asset_prices.insert(asset_symbol, price);
+        // Epoch e holds price e, so the average over the window is its middle epoch
+        let epoch = last_epoch + 1;
+        let expected = Decimal::from(2 * epoch - MAX_TWAP_WINDOW - 1) / Decimal::from(2);
+        assert_eq!(feed.twap(epoch, MAX_TWAP_WINDOW), Some(expected));
     }
 }
@@ -143,7 +143,7 @@ blueprint! {
             }
 
             for price in last_price {
-                self.market_prices.update(order.token_symbol(), price);
+                self.market_prices.update(order.token.address(), Context::current_epoch(), price);
             }
         }
 
@@ -334,6 +334,32 @@ blueprint! {
             info!(" \\------------------------------------------------------/");
         }
 
+        /// Returns the price of the last trade of the given token, if it has been traded at all.
+        pub fn get_last_price(&self, token: Address) -> Option<Decimal> {
+            self.market_prices.get(token)
+        }
+
+        /// Returns the number of epochs since the given token was last traded.
+        /// Consumers should treat prices older than they can tolerate as stale.
+        pub fn get_price_age(&self, token: Address) -> Option<u64> {
+            self.market_prices.age(token, Context::current_epoch())
+        }
+
+        /// Returns the time-weighted average price of the given token over the last `window_epochs` epochs
+        /// (excluding the current one). A window of 0 yields the last trade price.
+        ///
+        /// Returns None if the token hasn't been traded for the whole window yet, or the window is longer than
+        /// `MAX_TWAP_WINDOW` epochs.
+        pub fn get_twap(&self, token: Address, window_epochs: u64) -> Option<Decimal> {
+            self.market_prices.twap(token, Context::current_epoch(), window_epochs)
+        }
+
+        /// Returns the recent recorded prices of the given token, oldest first (at most one per epoch,
+        /// at most `MAX_TWAP_WINDOW + 1` of them).
+        pub fn get_price_history(&self, token: Address) -> Vec<PriceObservation> {
+            self.market_prices.history(token)
+        }
+
         pub fn print_market_prices(&self) {
             info!(" /' MARKET PRICES '\\");
             info!(" +-----------------+");
@@ -341,7 +367,7 @@ blueprint! {
             info!(" +-----------------+");
 
             for asset in self.market_prices.assets() {
-                let name = asset.clone();
+                let name = ResourceDef::from(asset).metadata()["symbol"].clone();
                 let price = self.market_prices.get(asset).unwrap();
 
                 info!(" | {:>5} | {:>7} |", name, self.truncate(price.to_string(), 7));
@@ -1,3 +1,6 @@
+use std::collections::VecDeque;
+
+use crate::model::FeeSchedule;
 use crate::trading_pair::*;
 use scrypto::prelude::*;
 
@@ -39,12 +42,16 @@ blueprint! {
         }
 
         /// Adds a trading pair for the given base_resource_address and quote_resource_address.
+        /// The trading pair charges fees according to the given fee_schedule. The fees it collects can be withdrawn
+        /// with the admin badge of this component. Its trades are aggregated into candles covering candle_interval epochs.
         /// Checks that the same trading pair cannot be added twice.
         /// Does not check that the reverse of an existing trading pair is not added!
         pub fn add_trading_pair(
             &mut self,
             base_resource_address: ResourceAddress,
             quote_resource_address: ResourceAddress,
+            fee_schedule: FeeSchedule,
+            candle_interval: u64,
         ) {
             // Prevent the same trading pair from being added twice
             assert!(
@@ -60,6 +67,9 @@ blueprint! {
             let trainding_pair_component = TradingPair::instantiate(
                 base_resource_address,
                 quote_resource_address,
+                self.admin_badge,
+                fee_schedule,
+                candle_interval,
             );
 
             // Save the info on the newly created trading pair in the trading_pairs HashMap
@@ -89,6 +99,120 @@ blueprint! {
                 .get(&(base_resource_address, quote_resource_address))
                 .map(|trading_pair| trading_pair.component_address)
         }
+
+        /// Returns the addresses of the trading pair components that a market order would be routed through in order to
+        /// exchange input_resource_address for target_resource_address, in the order they would be traded on.
+        /// Returns None if no route exists.
+        pub fn get_route(
+            &self,
+            input_resource_address: ResourceAddress,
+            target_resource_address: ResourceAddress,
+        ) -> Option<Vec<ComponentAddress>> {
+            let route = self.find_route(input_resource_address, target_resource_address)?;
+            Some(route.into_iter().map(|(component_address, _)| component_address).collect())
+        }
+
+        /// Exchanges the given input funds for the target resource by executing market orders on a route through the
+        /// registered trading pairs, e.g. A/XRD and then XRD/B in order to exchange A for B. The route with the fewest
+        /// trading pairs is chosen. Each market order is executed against the order book of the respective trading pair
+        /// and its output is used as the input of the next market order.
+        ///
+        /// Returns two values
+        /// 1. A bucket with the target resource
+        /// 2. Any dust that remained from the individual market orders
+        ///
+        /// Panics if no route exists, if any of the market orders cannot be filled or if less than min_out of the target
+        /// resource is received.
+        pub fn route_market_order(
+            &mut self,
+            input: Bucket,
+            target_resource_address: ResourceAddress,
+            min_out: Decimal,
+        ) -> (Bucket, Vec<Bucket>) {
+            let route = self
+                .find_route(input.resource_address(), target_resource_address)
+                .expect("No route found between the input resource and the target resource");
+
+            let mut funds = input;
+            let mut dust: Vec<Bucket> = Vec::new();
+            for (component_address, output_resource_address) in route {
+                let (unspent, traded): (Bucket, Option<Bucket>) = borrow_component!(component_address)
+                    .call::<(Bucket, Option<Bucket>)>("new_market_order", args![funds, None::<Proof>]);
+
+                if !unspent.is_empty() {
+                    dust.push(unspent);
+                }
+                funds = traded.expect("Market order did not yield any funds");
+                assert_eq!(funds.resource_address(), output_resource_address);
+            }
+
+            assert!(
+                funds.amount() >= min_out,
+                "Insufficient output: received {} but expected at least {}",
+                funds.amount(),
+                min_out
+            );
+
+            (funds, dust)
+        }
+
+        /// Finds the route with the fewest trading pairs that exchanges the input resource for the target resource.
+        /// Each trading pair can be traded in both directions.
+        /// Returns the component address of each trading pair on the route together with the resource that is received
+        /// from it, or None if no route exists.
+        fn find_route(
+            &self,
+            input_resource_address: ResourceAddress,
+            target_resource_address: ResourceAddress,
+        ) -> Option<Vec<(ComponentAddress, ResourceAddress)>> {
+            assert!(
+                input_resource_address != target_resource_address,
+                "The input resource and the target resource must be different"
+            );
+
+            // Breadth first search over the resources, where the trading pairs are the edges between them.
+            // For every resource reached, remember the resource and the trading pair it has been reached from.
+            let mut reached_from: HashMap<ResourceAddress, (ResourceAddress, ComponentAddress)> = HashMap::new();
+            let mut queue: VecDeque<ResourceAddress> = VecDeque::new();
+            queue.push_back(input_resource_address);
+            while let Some(resource_address) = queue.pop_front() {
+                if resource_address == target_resource_address {
+                    break;
+                }
+
+                for trading_pair in self.trading_pairs.values() {
+                    let next_resource_address = if trading_pair.base_resource_address == resource_address {
+                        trading_pair.quote_resource_address
+                    } else if trading_pair.quote_resource_address == resource_address {
+                        trading_pair.base_resource_address
+                    } else {
+                        continue;
+                    };
+
+                    if next_resource_address != input_resource_address
+                        && !reached_from.contains_key(&next_resource_address)
+                    {
+                        reached_from.insert(
+                            next_resource_address,
+                            (resource_address, trading_pair.component_address),
+                        );
+                        queue.push_back(next_resource_address);
+                    }
+                }
+            }
+
+            // Walk back from the target resource to the input resource
+            let mut route = Vec::new();
+            let mut resource_address = target_resource_address;
+            while resource_address != input_resource_address {
+                let (previous_resource_address, component_address) = reached_from.get(&resource_address)?;
+                route.push((*component_address, resource_address));
+                resource_address = *previous_resource_address;
+            }
+            route.reverse();
+
+            Some(route)
+        }
     }
 }
 
@@ -0,0 +1,203 @@
+use std::cmp::{max, min};
+
+use scrypto::prelude::*;
+
+use crate::model::Side;
+
+/// Represents a single trade, i.e. a limit order that has been (partially) filled by a taker order.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Debug)]
+pub struct Trade {
+    /// The price at which the trade has happened
+    pub price: Decimal,
+
+    /// The traded quantity, expressed in the base resource
+    pub quantity: Decimal,
+
+    /// The side of the taker order. A Bid taker has bought the base resource, an Ask taker has sold it.
+    pub taker_side: Side,
+
+    /// The epoch in which the trade has happened
+    pub epoch: u64,
+}
+
+/// Represents the open, high, low and close prices as well as the volume of all trades within an interval of epochs.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Debug, PartialEq)]
+pub struct Candle {
+    /// The first epoch of the interval
+    pub start_epoch: u64,
+
+    /// The price of the first trade in the interval
+    pub open: Decimal,
+
+    /// The highest price of all trades in the interval
+    pub high: Decimal,
+
+    /// The lowest price of all trades in the interval
+    pub low: Decimal,
+
+    /// The price of the last trade in the interval
+    pub close: Decimal,
+
+    /// The traded quantity in the interval, expressed in the base resource
+    pub volume: Decimal,
+
+    /// The traded quantity in the interval, expressed in the quote resource
+    pub quote_volume: Decimal,
+}
+
+impl Candle {
+    /// Creates a new candle for the interval starting at the given epoch from the first trade in that interval
+    pub fn new(start_epoch: u64, trade: &Trade) -> Self {
+        Self {
+            start_epoch,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.quantity,
+            quote_volume: trade.quantity * trade.price,
+        }
+    }
+
+    /// Adds the given trade to this candle
+    pub fn add_trade(&mut self, trade: &Trade) {
+        self.high = max(self.high, trade.price);
+        self.low = min(self.low, trade.price);
+        self.close = trade.price;
+        self.volume += trade.quantity;
+        self.quote_volume += trade.quantity * trade.price;
+    }
+}
+
+/// Records all trades of a trading pair and aggregates them into candles.
+/// Trades and candles are stored in lazy maps, keyed by their sequence number, so that the history can grow
+/// without bloating the state of the component.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe)]
+pub(crate) struct TradeHistory {
+    /// All trades, keyed by their sequence number
+    trades: LazyMap<u64, Trade>,
+
+    /// The number of trades that have been recorded
+    trade_count: u64,
+
+    /// The number of epochs that each candle covers
+    candle_interval: u64,
+
+    /// All candles, keyed by their sequence number. Intervals without any trades do not have a candle.
+    candles: LazyMap<u64, Candle>,
+
+    /// The number of candles that have been recorded
+    candle_count: u64,
+}
+
+impl TradeHistory {
+    /// Creates a new empty trade history with candles covering the given number of epochs
+    /// Panics if candle_interval is zero
+    pub fn new(candle_interval: u64) -> Self {
+        assert!(candle_interval > 0, "Parameter candle_interval must be > zero");
+        Self {
+            trades: LazyMap::new(),
+            trade_count: 0,
+            candle_interval,
+            candles: LazyMap::new(),
+            candle_count: 0,
+        }
+    }
+
+    /// Records the given trade and adds it to the candle of its interval
+    pub fn record_trade(&mut self, trade: Trade) {
+        let start_epoch = trade.epoch - trade.epoch % self.candle_interval;
+        let last_candle = match self.candle_count {
+            0 => None,
+            count => self.candles.get(&(count - 1)),
+        };
+        match last_candle {
+            Some(mut candle) if candle.start_epoch == start_epoch => {
+                candle.add_trade(&trade);
+                self.candles.insert(self.candle_count - 1, candle);
+            }
+            _ => {
+                self.candles
+                    .insert(self.candle_count, Candle::new(start_epoch, &trade));
+                self.candle_count += 1;
+            }
+        }
+
+        self.trades.insert(self.trade_count, trade);
+        self.trade_count += 1;
+    }
+
+    /// Returns the number of trades that have been recorded
+    pub fn trade_count(&self) -> u64 {
+        self.trade_count
+    }
+
+    /// Returns the number of candles that have been recorded
+    pub fn candle_count(&self) -> u64 {
+        self.candle_count
+    }
+
+    /// Returns up to limit trades, starting with the trade with the given sequence number. Oldest trades come first.
+    pub fn get_trades(&self, offset: u64, limit: u32) -> Vec<Trade> {
+        page(offset, limit, self.trade_count)
+            .map(|index| self.trades.get(&index).unwrap())
+            .collect()
+    }
+
+    /// Returns up to limit candles, starting with the candle with the given sequence number. Oldest candles come first.
+    pub fn get_candles(&self, offset: u64, limit: u32) -> Vec<Candle> {
+        page(offset, limit, self.candle_count)
+            .map(|index| self.candles.get(&index).unwrap())
+            .collect()
+    }
+}
+
+/// Returns the sequence numbers of the page with the given offset and limit in a collection with the given number of items
+fn page(offset: u64, limit: u32, count: u64) -> std::ops::Range<u64> {
+    let start = min(offset, count);
+    let end = min(start.saturating_add(limit as u64), count);
+    start..end
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trade(price: i32, quantity: i32) -> Trade {
+        Trade {
+            price: price.into(),
+            quantity: quantity.into(),
+            taker_side: Side::Bid,
+            epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_candle_aggregates_trades() {
+        let mut candle = Candle::new(10, &trade(10, 1));
+        candle.add_trade(&trade(12, 2));
+        candle.add_trade(&trade(8, 1));
+        candle.add_trade(&trade(9, 1));
+
+        assert_eq!(
+            candle,
+            Candle {
+                start_epoch: 10,
+                open: 10.into(),
+                high: 12.into(),
+                low: 8.into(),
+                close: 9.into(),
+                volume: 5.into(),
+                quote_volume: 51.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_page() {
+        assert_eq!(page(0, 10, 5), 0..5);
+        assert_eq!(page(2, 2, 5), 2..4);
+        assert_eq!(page(7, 2, 5), 5..5);
+        assert_eq!(page(u64::MAX, u32::MAX, 5), 5..5);
+    }
+}
@@ -1,3 +1,4 @@
 mod dex;
+mod history;
 mod model;
 mod trading_pair;
@@ -4,7 +4,7 @@ use scrypto::prelude::*;
 /// In an example trading pair XRD/rUSD an Ask order would represent a user asking to receive rUSD as payment for their XRD.
 /// Conversely, a Bid order would represent a user bidding their rUSD and expecting to receive XRD in return.
 #[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Copy, Debug)]
-pub(crate) enum Side {
+pub enum Side {
     Ask,
     Bid,
 }
@@ -17,9 +17,115 @@ impl Side {
             Self::Bid => Self::Ask,
         }
     }
+
+    /// Determines whether an order of this side with the given price would be matched against a limit order on the
+    /// opposite side of the order book with the given price.
+    pub fn crosses(&self, price: Decimal, opposite_price: Decimal) -> bool {
+        match self {
+            Self::Ask => price <= opposite_price,
+            Self::Bid => price >= opposite_price,
+        }
+    }
+}
+
+/// Represents the time in force of a limit order, i.e. how the order is executed and for how long it remains active.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Copy, Debug)]
+pub enum TimeInForce {
+    /// The part of the order that can be matched is executed immediately, the remainder rests in the order book
+    /// until the order is closed.
+    GoodTilCanceled,
+
+    /// Like GoodTilCanceled, but the order can no longer be matched after the given epoch
+    GoodTilEpoch(u64),
+
+    /// The order is rejected if any part of it would be matched immediately
+    PostOnly,
+
+    /// The part of the order that can be matched is executed immediately, the remainder is refunded
+    ImmediateOrCancel,
+
+    /// The order is executed immediately and completely or not at all
+    FillOrKill,
+}
+
+/// Represents the fees that a trading pair charges on trades.
+/// All fees are specified in basis points, i.e. a fee of 25 basis points equals 0.25%.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Debug)]
+pub struct FeeSchedule {
+    /// The fee that is charged on the amount a maker receives when their limit order is filled
+    pub maker_fee_bps: u16,
+
+    /// The fee that is charged on the amount a taker receives when their order is executed against the order book
+    pub taker_fee_bps: u16,
+
+    /// Holders of this badge are granted a discount on maker and taker fees. None if no discount is offered.
+    pub discount_badge: Option<ResourceAddress>,
+
+    /// The discount granted to holders of the discount badge. This is specified relative to the regular fee,
+    /// i.e. a discount of 5000 basis points halves the fees.
+    pub discount_bps: u16,
+}
+
+impl FeeSchedule {
+    /// Panics if any of the fees or the discount exceeds 10000 basis points
+    pub fn validate(&self) {
+        assert!(self.maker_fee_bps <= 10_000, "Maker fee must not exceed 10000 basis points");
+        assert!(self.taker_fee_bps <= 10_000, "Taker fee must not exceed 10000 basis points");
+        assert!(self.discount_bps <= 10_000, "Fee discount must not exceed 10000 basis points");
+    }
+
+    /// Returns the maker fee as a fraction of the traded amount
+    pub fn maker_fee(&self, discounted: bool) -> Decimal {
+        self.apply_discount(bps_to_fraction(self.maker_fee_bps), discounted)
+    }
+
+    /// Returns the taker fee as a fraction of the traded amount
+    pub fn taker_fee(&self, discounted: bool) -> Decimal {
+        self.apply_discount(bps_to_fraction(self.taker_fee_bps), discounted)
+    }
+
+    fn apply_discount(&self, fee: Decimal, discounted: bool) -> Decimal {
+        if discounted {
+            fee * (Decimal::one() - bps_to_fraction(self.discount_bps))
+        } else {
+            fee
+        }
+    }
+}
+
+/// Converts the given amount of basis points into a fraction
+fn bps_to_fraction(bps: u16) -> Decimal {
+    Decimal::from(bps) / Decimal::from(10_000)
+}
+
+/// Represents the type of an order.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Copy, Debug)]
+pub(crate) enum OrderType {
+    /// A plain limit order that is placed in the order book directly
+    Limit,
+
+    /// A stop order that is converted into a market order once a trade crosses the trigger price
+    StopMarket { trigger_price: Decimal },
+
+    /// A stop order that is converted into a limit order (at the order's price) once a trade crosses the trigger price
+    StopLimit { trigger_price: Decimal },
+}
+
+/// Represents the status of an order, i.e. in which book (if any) the order currently lives.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OrderStatus {
+    /// The order is a stop order that sits in the trigger book and waits to be triggered
+    Dormant,
+
+    /// The order rests in the order book and can be matched against market orders
+    Open,
+
+    /// The order does not live in any book anymore. It can only be closed in order to redeem its funds.
+    Closed,
 }
 
 /// Represent a limit order in the order book.
+/// Stop orders are represented by the same type. They only differ in their order type and initial status.
 #[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, NonFungibleData)]
 pub(crate) struct LimitOrder {
     /// A key that uniquely identifies the order
@@ -28,20 +134,51 @@ pub(crate) struct LimitOrder {
     /// The side of the order
     pub side: Side,
 
+    /// The type of the order
+    pub order_type: OrderType,
+
     /// The amount of the quote resource that must be paid to get one unit of the base resource.
     /// For a trading pair XRD/rUSD a price of 10 would mean that 10 rUSD would have to be paid in order to buy 1 XRD.
     /// The price is always defined as stated above, irrespective of the order side!
+    /// For stop market orders, this is the trigger price.
+    /// The price can be changed by amending the order.
+    #[scrypto(mutable)]
     pub price: Decimal,
 
     /// The amount of the resource that a user is providing to the DEX for their order.
     /// For a trading pair XRD/rUSD and an Ask order this would be the amount of XRD, as the user is asking rUSD for the XRD they are selling.
     /// For a Bid order this would be the amount of rUSD, as the user is bidding rUSD for the XRD they are wanting to buy.
     /// Note that this is asymmetric with respect to the price!
+    /// The quantity can be reduced by amending the order.
+    #[scrypto(mutable)]
     pub quantity: Decimal,
 
+    /// The last epoch in which the order can be matched. None if the order does not expire.
+    pub expiry_epoch: Option<u64>,
+
+    /// The fee (as a fraction of the traded amount) that is charged when the order is filled by a market order
+    pub maker_fee: Decimal,
+
+    /// The fee (as a fraction of the traded amount) that is charged when the order itself is executed against
+    /// existing limit orders, e.g. after a stop order has been triggered
+    pub taker_fee: Decimal,
+
     /// The amount of the order quantity that has already been filled.
     #[scrypto(mutable)]
     pub quantity_filled: Decimal,
+
+    /// The amount of the opposite resource that the order has received in exchange for the filled quantity.
+    /// This amount includes the fees.
+    #[scrypto(mutable)]
+    pub traded_amount: Decimal,
+
+    /// The part of the traded amount that is owed as fees
+    #[scrypto(mutable)]
+    pub fee_amount: Decimal,
+
+    /// The status of the order
+    #[scrypto(mutable)]
+    pub status: OrderStatus,
 }
 
 impl LimitOrder {
@@ -58,33 +195,106 @@ impl LimitOrder {
         LimitOrder {
             order_key,
             side,
+            order_type: OrderType::Limit,
             price,
             quantity,
+            expiry_epoch: None,
+            maker_fee: Decimal::zero(),
+            taker_fee: Decimal::zero(),
             quantity_filled: Decimal::zero(),
+            traded_amount: Decimal::zero(),
+            fee_amount: Decimal::zero(),
+            status: OrderStatus::Open,
         }
     }
 
+    /// Creates a new stop order that is triggered at the given trigger price.
+    /// If a limit price is given, the order is converted into a limit order at that price once triggered.
+    /// Otherwise it is converted into a market order.
+    /// Panics if trigger_price, limit_price or quantity are <= 0
+    pub fn new_stop(
+        order_key: NonFungibleId,
+        side: Side,
+        trigger_price: Decimal,
+        limit_price: Option<Decimal>,
+        quantity: Decimal,
+    ) -> LimitOrder {
+        assert!(
+            trigger_price.is_positive(),
+            "Parameter trigger_price must be > zero"
+        );
+        let (order_type, price) = match limit_price {
+            Some(limit_price) => (OrderType::StopLimit { trigger_price }, limit_price),
+            None => (OrderType::StopMarket { trigger_price }, trigger_price),
+        };
+
+        let mut order = LimitOrder::new(order_key, side, price, quantity);
+        order.order_type = order_type;
+        order.status = OrderStatus::Dormant;
+        order
+    }
+
+    /// Returns the trigger price of a stop order or None if this is a plain limit order
+    pub fn trigger_price(&self) -> Option<Decimal> {
+        match self.order_type {
+            OrderType::Limit => None,
+            OrderType::StopMarket { trigger_price } => Some(trigger_price),
+            OrderType::StopLimit { trigger_price } => Some(trigger_price),
+        }
+    }
+
+    /// Determines whether the order has expired, i.e. whether it can no longer be matched in the given epoch
+    pub fn is_expired(&self, current_epoch: u64) -> bool {
+        match self.expiry_epoch {
+            Some(expiry_epoch) => current_epoch > expiry_epoch,
+            None => false,
+        }
+    }
+
+    /// Returns the quantity of the order that has not been filled yet
+    pub fn unfilled_quantity(&self) -> Decimal {
+        self.quantity - self.quantity_filled
+    }
+
     /// Fill the market order with the given quantity
     /// Panics if the given quantity would "overfill" the order
     pub fn fill(&mut self, quantity: Decimal) {
+        let traded_amount = match self.side {
+            Side::Ask => quantity * self.price,
+            Side::Bid => quantity / self.price,
+        };
+        self.record_fill(quantity, traded_amount, traded_amount * self.maker_fee);
+    }
+
+    /// Records a trade in which this order has been executed against existing limit orders, e.g. after a
+    /// stop order has been triggered. In that case the traded amount does not follow from the order's own price
+    /// and the taker fee applies.
+    /// Panics if the given quantity would "overfill" the order
+    pub fn fill_as_taker(&mut self, quantity: Decimal, traded_amount: Decimal) {
+        self.record_fill(quantity, traded_amount, traded_amount * self.taker_fee);
+    }
+
+    fn record_fill(&mut self, quantity: Decimal, traded_amount: Decimal, fee_amount: Decimal) {
         assert!(
-            quantity <= self.quantity - self.quantity_filled,
+            quantity <= self.unfilled_quantity(),
             "The fill quantity is too high"
         );
         self.quantity_filled += quantity;
+        self.traded_amount += traded_amount;
+        self.fee_amount += fee_amount;
     }
 
     /// Calculate the amounts of resources the user will receive upon closing the order.
     /// The first value returned is the amount the user will be refunded in case the order has not been filled fully.
     /// The second value returned is the amount that the user has successfully traded/received when the order was filled/partially filled.
-    pub fn calculate_close_amounts(&self) -> (Decimal, Decimal) {
-        let refund_amount = self.quantity - self.quantity_filled;
-        let traded_amount = match self.side {
-            Side::Ask => self.quantity_filled * self.price,
-            Side::Bid => self.quantity_filled / self.price,
-        };
-
-        (refund_amount, traded_amount)
+    /// Fees have already been deducted from this amount.
+    /// The third value returned is the amount of fees that has been charged on the traded amount.
+    pub fn calculate_close_amounts(&self) -> (Decimal, Decimal, Decimal) {
+        (
+            self.unfilled_quantity(),
+            self.traded_amount - self.fee_amount,
+            self.fee_amount,
+        )
     }
 }
 
@@ -97,6 +307,11 @@ struct OrderBookSide {
     /// The orders on this side of the order book
     /// Keys represent the price level while values are vectors holding the keys of all orders that live on this price level.
     orders: BTreeMap<Decimal, Vec<NonFungibleId>>,
+
+    /// The aggregated unfilled quantity of all orders on this side of the order book.
+    /// Keys represent the price level while values are the sum of the unfilled quantities of all orders on that price level.
+    /// This allows querying the depth of the order book without loading the data of every order.
+    quantities: BTreeMap<Decimal, Decimal>,
 }
 
 impl OrderBookSide {
@@ -104,6 +319,7 @@ impl OrderBookSide {
         Self {
             side,
             orders: BTreeMap::new(),
+            quantities: BTreeMap::new(),
         }
     }
 
@@ -130,6 +346,40 @@ impl OrderBookSide {
         Some(*price)
     }
 
+    /// Returns an iterator over the keys of all orders on this side of the order book.
+    /// The best price level comes first and orders on the same price level are returned in the order they were inserted.
+    fn iter_orders(&self) -> Box<dyn Iterator<Item = &NonFungibleId> + '_> {
+        match self.side {
+            Side::Ask => Box::new(self.orders.values().flatten()),
+            Side::Bid => Box::new(self.orders.values().rev().flatten()),
+        }
+    }
+
+    /// Returns up to the given number of price levels on this side of the order book, best price level first.
+    fn get_depth(&self, levels: usize) -> Vec<PriceLevel> {
+        let price_levels: Box<dyn Iterator<Item = (&Decimal, &Vec<NonFungibleId>)> + '_> = match self.side {
+            Side::Ask => Box::new(self.orders.iter()),
+            Side::Bid => Box::new(self.orders.iter().rev()),
+        };
+
+        price_levels
+            .take(levels)
+            .map(|(price, orders)| {
+                let quantity = self.quantities[price];
+                let (base_quantity, quote_quantity) = match self.side {
+                    Side::Ask => (quantity, quantity * *price),
+                    Side::Bid => (quantity / *price, quantity),
+                };
+                PriceLevel {
+                    price: *price,
+                    base_quantity,
+                    quote_quantity,
+                    order_count: orders.len() as u32,
+                }
+            })
+            .collect()
+    }
+
     /// Inserts the given limit order into this side of the order book
     fn insert_limit_order(&mut self, order: &LimitOrder) {
         match self.orders.get_mut(&order.price) {
@@ -139,6 +389,12 @@ impl OrderBookSide {
                     .insert(order.price, vec![order.order_key.clone()]);
             }
         }
+        *self.quantities.entry(order.price).or_insert_with(Decimal::zero) += order.unfilled_quantity();
+    }
+
+    /// Records that the unfilled quantity of an order on the given price level has been reduced by the given quantity
+    fn reduce_order(&mut self, price: Decimal, quantity: Decimal) {
+        *self.quantities.get_mut(&price).unwrap() -= quantity;
     }
 
     /// Removes the given order form this side of the order book
@@ -147,10 +403,39 @@ impl OrderBookSide {
         price_level.retain(|order_key| order_key.to_vec() != to_remove.order_key.to_vec());
         if price_level.is_empty() {
             self.orders.remove(&to_remove.price);
+            self.quantities.remove(&to_remove.price);
+        } else {
+            self.reduce_order(to_remove.price, to_remove.unfilled_quantity());
         }
     }
 }
 
+/// Represents the aggregated orders on one price level of the order book
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Debug)]
+pub struct PriceLevel {
+    /// The price of this price level
+    pub price: Decimal,
+
+    /// The unfilled quantity of all orders on this price level, expressed in the base resource
+    pub base_quantity: Decimal,
+
+    /// The unfilled quantity of all orders on this price level, expressed in the quote resource
+    pub quote_quantity: Decimal,
+
+    /// The number of orders on this price level
+    pub order_count: u32,
+}
+
+/// Represents a snapshot of the order book that holds the aggregated orders per price level
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Debug)]
+pub struct OrderBookDepth {
+    /// The price levels on the Ask side, lowest price first
+    pub asks: Vec<PriceLevel>,
+
+    /// The price levels on the Bid side, highest price first
+    pub bids: Vec<PriceLevel>,
+}
+
 /// Represents the order book for a trading pair
 #[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Debug)]
 pub(crate) struct OrderBook {
@@ -180,27 +465,56 @@ impl OrderBook {
         side.get_best_order()
     }
 
+    /// Returns the price of the best order in the order book for the given side. Returns None if no orders exists
+    /// on that side of the order book.
+    pub fn get_best_price(&self, side: Side) -> Option<Decimal> {
+        let side = match side {
+            Side::Ask => &self.asks,
+            Side::Bid => &self.bids,
+        };
+        side.get_best_price()
+    }
+
+    /// Returns up to the given number of price levels per side of the order book, best price levels first.
+    pub fn get_depth(&self, levels: usize) -> OrderBookDepth {
+        OrderBookDepth {
+            asks: self.asks.get_depth(levels),
+            bids: self.bids.get_depth(levels),
+        }
+    }
+
+    /// Returns an iterator over the keys of all orders on the given side of the order book, best orders first.
+    pub fn iter_orders(&self, side: Side) -> Box<dyn Iterator<Item = &NonFungibleId> + '_> {
+        match side {
+            Side::Ask => self.asks.iter_orders(),
+            Side::Bid => self.bids.iter_orders(),
+        }
+    }
+
     /// Inserts a limit order into the given side of the order book.
-    /// Panics if the order is priced such that it would constitute a market order.
+    /// Panics if the order is priced such that it would constitute a market order. Any part of an order that can be
+    /// matched must be executed before inserting it.
     pub fn insert_limit_order(&mut self, order: &LimitOrder) {
-        match order.side {
-            Side::Ask => {
-                let best_bid_price = self.bids.get_best_price();
-                assert!(
-                    best_bid_price.is_none() || order.price > best_bid_price.unwrap(),
-                    "Order would be a market order"
-                );
-                self.asks.insert_limit_order(order);
-            }
-            Side::Bid => {
-                let best_ask_price = self.asks.get_best_price();
-                assert!(
-                    best_ask_price.is_none() || order.price < best_ask_price.unwrap(),
-                    "Order would be a market order"
-                );
-                self.bids.insert_limit_order(order)
-            }
+        let (side, opposite_side) = match order.side {
+            Side::Ask => (&mut self.asks, &self.bids),
+            Side::Bid => (&mut self.bids, &self.asks),
         };
+        let best_opposite_price = opposite_side.get_best_price();
+        assert!(
+            best_opposite_price.is_none() || !order.side.crosses(order.price, best_opposite_price.unwrap()),
+            "Order would be a market order"
+        );
+        side.insert_limit_order(order);
+    }
+
+    /// Records that the unfilled quantity of the given limit order has been reduced by the given quantity, e.g. because
+    /// the order has been filled. This must be called whenever the unfilled quantity of an order in the order book changes.
+    pub fn reduce_limit_order(&mut self, order: &LimitOrder, quantity: Decimal) {
+        let side = match order.side {
+            Side::Ask => &mut self.asks,
+            Side::Bid => &mut self.bids,
+        };
+        side.reduce_order(order.price, quantity);
     }
 
     /// Removes the given limit order from the order book.
@@ -213,6 +527,82 @@ impl OrderBook {
     }
 }
 
+/// Represents the trigger book for a trading pair. It holds all stop orders that have not been triggered yet.
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Debug)]
+pub(crate) struct StopBook {
+    /// Stop orders on the Ask side. They are triggered once a trade happens at or below their trigger price.
+    /// Keys represent the trigger price while values are vectors holding the keys of all orders with that trigger price.
+    asks: BTreeMap<Decimal, Vec<NonFungibleId>>,
+
+    /// Stop orders on the Bid side. They are triggered once a trade happens at or above their trigger price.
+    /// Keys represent the trigger price while values are vectors holding the keys of all orders with that trigger price.
+    bids: BTreeMap<Decimal, Vec<NonFungibleId>>,
+}
+
+impl StopBook {
+    /// Creates a new empty trigger book
+    pub fn new() -> Self {
+        Self {
+            asks: BTreeMap::new(),
+            bids: BTreeMap::new(),
+        }
+    }
+
+    /// Determines whether a stop order of the given side and trigger price would be triggered by a trade at the given price
+    pub fn is_triggered(side: Side, trigger_price: Decimal, last_price: Decimal) -> bool {
+        match side {
+            Side::Ask => last_price <= trigger_price,
+            Side::Bid => last_price >= trigger_price,
+        }
+    }
+
+    /// Inserts the given stop order into the trigger book
+    /// Panics if the order is not a stop order
+    pub fn insert_stop_order(&mut self, order: &LimitOrder) {
+        let trigger_price = order.trigger_price().expect("Order is not a stop order");
+        let side = match order.side {
+            Side::Ask => &mut self.asks,
+            Side::Bid => &mut self.bids,
+        };
+        side.entry(trigger_price)
+            .or_insert_with(Vec::new)
+            .push(order.order_key.clone());
+    }
+
+    /// Removes the given stop order from the trigger book
+    pub fn remove_stop_order(&mut self, to_remove: &LimitOrder) {
+        let trigger_price = to_remove.trigger_price().expect("Order is not a stop order");
+        let side = match to_remove.side {
+            Side::Ask => &mut self.asks,
+            Side::Bid => &mut self.bids,
+        };
+        let trigger_level = side.get_mut(&trigger_price).unwrap();
+        trigger_level.retain(|order_key| order_key.to_vec() != to_remove.order_key.to_vec());
+        if trigger_level.is_empty() {
+            side.remove(&trigger_price);
+        }
+    }
+
+    /// Returns the key of a stop order that is triggered by a trade at the given price. Returns None if no such order exists.
+    /// Ask orders with the highest trigger price and bid orders with the lowest trigger price are returned first.
+    pub fn get_triggered_order(&self, last_price: Decimal) -> Option<&NonFungibleId> {
+        let triggered_ask = self
+            .asks
+            .iter()
+            .last()
+            .filter(|(trigger_price, _)| Self::is_triggered(Side::Ask, **trigger_price, last_price));
+        let triggered_bid = self
+            .bids
+            .iter()
+            .next()
+            .filter(|(trigger_price, _)| Self::is_triggered(Side::Bid, **trigger_price, last_price));
+
+        triggered_ask
+            .or(triggered_bid)
+            .and_then(|(_, orders)| orders.first())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -260,4 +650,200 @@ mod test {
             (-1).into(),
         );
     }
+
+    #[test]
+    #[should_panic(expected = "Parameter trigger_price must be > zero")]
+    fn test_stop_order_constructor_panics_on_zero_trigger_price() {
+        LimitOrder::new_stop(
+            NonFungibleId::from_str("1234").unwrap(),
+            Side::Ask,
+            0.into(),
+            None,
+            1.into(),
+        );
+    }
+
+    #[test]
+    fn test_close_amounts_include_taker_fills() {
+        let mut order = LimitOrder::new_stop(
+            NonFungibleId::from_str("1234").unwrap(),
+            Side::Ask,
+            10.into(),
+            None,
+            100.into(),
+        );
+        order.fill_as_taker(60.into(), 540.into());
+
+        let (refund_amount, traded_amount, fee_amount) = order.calculate_close_amounts();
+        assert_eq!(refund_amount, 40.into());
+        assert_eq!(traded_amount, 540.into());
+        assert_eq!(fee_amount, 0.into());
+    }
+
+    #[test]
+    fn test_close_amounts_deduct_fees() {
+        let fee_schedule = FeeSchedule {
+            maker_fee_bps: 10,
+            taker_fee_bps: 20,
+            discount_badge: None,
+            discount_bps: 5000,
+        };
+        let mut order = LimitOrder::new(
+            NonFungibleId::from_str("1234").unwrap(),
+            Side::Ask,
+            10.into(),
+            100.into(),
+        );
+        order.maker_fee = fee_schedule.maker_fee(false);
+        order.taker_fee = fee_schedule.taker_fee(true);
+        order.fill(50.into());
+        order.fill_as_taker(50.into(), 1000.into());
+
+        // 500 * 0.1% maker fee + 1000 * 0.1% discounted taker fee
+        let (refund_amount, traded_amount, fee_amount) = order.calculate_close_amounts();
+        assert_eq!(refund_amount, 0.into());
+        assert_eq!(traded_amount, "1498.5".into());
+        assert_eq!(fee_amount, "1.5".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Taker fee must not exceed 10000 basis points")]
+    fn test_fee_schedule_validate_panics_on_excessive_fee() {
+        FeeSchedule {
+            maker_fee_bps: 0,
+            taker_fee_bps: 10_001,
+            discount_badge: None,
+            discount_bps: 0,
+        }
+        .validate();
+    }
+
+    #[test]
+    fn test_stop_book_triggers_orders() {
+        let mut stop_book = StopBook::new();
+        let stop_loss = LimitOrder::new_stop(
+            NonFungibleId::from_str("1234").unwrap(),
+            Side::Ask,
+            90.into(),
+            None,
+            1.into(),
+        );
+        let stop_buy = LimitOrder::new_stop(
+            NonFungibleId::from_str("5678").unwrap(),
+            Side::Bid,
+            110.into(),
+            Some(115.into()),
+            1.into(),
+        );
+        stop_book.insert_stop_order(&stop_loss);
+        stop_book.insert_stop_order(&stop_buy);
+
+        assert!(stop_book.get_triggered_order(100.into()).is_none());
+        assert_eq!(
+            stop_book.get_triggered_order(90.into()).unwrap().to_vec(),
+            stop_loss.order_key.to_vec()
+        );
+        assert_eq!(
+            stop_book.get_triggered_order(120.into()).unwrap().to_vec(),
+            stop_buy.order_key.to_vec()
+        );
+
+        stop_book.remove_stop_order(&stop_loss);
+        assert!(stop_book.get_triggered_order(80.into()).is_none());
+    }
+
+    #[test]
+    fn test_limit_order_expiry() {
+        let mut order = LimitOrder::new(
+            NonFungibleId::from_str("1234").unwrap(),
+            Side::Bid,
+            1.into(),
+            1.into(),
+        );
+        assert!(!order.is_expired(u64::MAX));
+
+        order.expiry_epoch = Some(10);
+        assert!(!order.is_expired(9));
+        assert!(!order.is_expired(10));
+        assert!(order.is_expired(11));
+    }
+
+    #[test]
+    fn test_side_crosses() {
+        assert!(Side::Bid.crosses(10.into(), 10.into()));
+        assert!(Side::Bid.crosses(11.into(), 10.into()));
+        assert!(!Side::Bid.crosses(9.into(), 10.into()));
+        assert!(Side::Ask.crosses(10.into(), 10.into()));
+        assert!(Side::Ask.crosses(9.into(), 10.into()));
+        assert!(!Side::Ask.crosses(11.into(), 10.into()));
+    }
+
+    #[test]
+    fn test_iter_orders_returns_best_orders_first() {
+        let mut order_book = OrderBook::new();
+        for (key, side, price) in vec![
+            ("01", Side::Ask, 11),
+            ("02", Side::Ask, 10),
+            ("03", Side::Ask, 10),
+            ("04", Side::Bid, 8),
+            ("05", Side::Bid, 9),
+        ] {
+            order_book.insert_limit_order(&LimitOrder::new(
+                NonFungibleId::from_str(key).unwrap(),
+                side,
+                price.into(),
+                1.into(),
+            ));
+        }
+
+        let asks: Vec<Vec<u8>> = order_book.iter_orders(Side::Ask).map(|key| key.to_vec()).collect();
+        let bids: Vec<Vec<u8>> = order_book.iter_orders(Side::Bid).map(|key| key.to_vec()).collect();
+        let expected_keys = |keys: Vec<&str>| -> Vec<Vec<u8>> {
+            keys.into_iter()
+                .map(|key| NonFungibleId::from_str(key).unwrap().to_vec())
+                .collect()
+        };
+        assert_eq!(asks, expected_keys(vec!["02", "03", "01"]));
+        assert_eq!(bids, expected_keys(vec!["05", "04"]));
+    }
+
+    #[test]
+    fn test_get_depth_aggregates_price_levels() {
+        let mut order_book = OrderBook::new();
+        let mut orders = vec![];
+        for (key, side, price, quantity) in vec![
+            ("01", Side::Ask, 11, 5),
+            ("02", Side::Ask, 10, 1),
+            ("03", Side::Ask, 10, 2),
+            ("04", Side::Bid, 8, 80),
+            ("05", Side::Bid, 9, 90),
+        ] {
+            let order = LimitOrder::new(
+                NonFungibleId::from_str(key).unwrap(),
+                side,
+                price.into(),
+                quantity.into(),
+            );
+            order_book.insert_limit_order(&order);
+            orders.push(order);
+        }
+
+        // Fill one order partially and remove another one
+        orders[1].fill(Decimal::one());
+        order_book.reduce_limit_order(&orders[1], Decimal::one());
+        order_book.remove_limit_order(&orders[4]);
+
+        let depth = order_book.get_depth(1);
+        assert_eq!(depth.asks.len(), 1);
+        assert_eq!(depth.asks[0].price, 10.into());
+        assert_eq!(depth.asks[0].base_quantity, 2.into());
+        assert_eq!(depth.asks[0].quote_quantity, 20.into());
+        assert_eq!(depth.asks[0].order_count, 2);
+        assert_eq!(depth.bids.len(), 1);
+        assert_eq!(depth.bids[0].price, 8.into());
+        assert_eq!(depth.bids[0].base_quantity, 10.into());
+        assert_eq!(depth.bids[0].quote_quantity, 80.into());
+
+        assert_eq!(order_book.get_depth(10).asks.len(), 2);
+    }
 }
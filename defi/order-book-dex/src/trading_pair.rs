@@ -2,6 +2,7 @@ use std::cmp::min;
 
 use scrypto::prelude::*;
 
+use crate::history::*;
 use crate::model::*;
 
 blueprint! {
@@ -18,19 +19,47 @@ blueprint! {
         /// The order book that holds all limit orders that are created for this trading pair
         order_book: OrderBook,
 
+        /// The trigger book that holds all stop orders that have not been triggered yet
+        stop_book: StopBook,
+
+        /// The price at which the last trade has happened. None if no trade has happened yet.
+        last_price: Option<Decimal>,
+
+        /// The history of all trades that have happened on this trading pair
+        history: TradeHistory,
+
         /// The resources that market makers have deposited into this component by creating limit ask orders
         base_funds: Vault,
 
         /// The resources that market makers have deposited into this component by creating limit bid orders
         quote_funds: Vault,
+
+        /// The fees that are charged on trades
+        fee_schedule: FeeSchedule,
+
+        /// The fees that have been collected in the base resource
+        base_fees: Vault,
+
+        /// The fees that have been collected in the quote resource
+        quote_fees: Vault,
     }
 
     impl TradingPair {
         /// Instantiates a new TradingPair component for the given base_resource and quote_resource.
-        /// To create a trading pair XRD/rUSD, one would issue a call like this: `instantiate(xrd_address, rusd_address)`
-        pub fn instantiate(base_resource: ResourceAddress, quote_resource: ResourceAddress) -> ComponentAddress {
+        /// To create a trading pair XRD/rUSD, one would issue a call like this:
+        /// `instantiate(xrd_address, rusd_address, admin_badge, fee_schedule, candle_interval)`
+        /// The collected fees can be withdrawn by the holder of the given admin badge.
+        /// Trades are aggregated into candles that cover the given number of epochs.
+        pub fn instantiate(
+            base_resource: ResourceAddress,
+            quote_resource: ResourceAddress,
+            admin_badge: ResourceAddress,
+            fee_schedule: FeeSchedule,
+            candle_interval: u64,
+        ) -> ComponentAddress {
             assert_is_fungible(&base_resource);
             assert_is_fungible(&quote_resource);
+            fee_schedule.validate();
 
             let minter =
                 ResourceBuilder::new_fungible().divisibility(DIVISIBILITY_NONE).initial_supply(1);
@@ -51,14 +80,26 @@ blueprint! {
                 .updateable_non_fungible_data(rule!(require(minter.resource_address())), LOCKED)
                 .no_initial_supply();
 
-            Self {
+            let component = Self {
                 minter: Vault::with_bucket(minter),
                 order_resource,
                 order_book: OrderBook::new(),
+                stop_book: StopBook::new(),
+                last_price: None,
+                history: TradeHistory::new(candle_interval),
                 base_funds: Vault::new(base_resource),
                 quote_funds: Vault::new(quote_resource),
+                fee_schedule,
+                base_fees: Vault::new(base_resource),
+                quote_fees: Vault::new(quote_resource),
             }
-            .instantiate().globalize()
+            .instantiate();
+
+            let access_rules = AccessRules::new()
+                .method("withdraw_fees", rule!(require(admin_badge)))
+                .default(rule!(allow_all));
+
+            component.add_access_check(access_rules).globalize()
         }
 
         /// Creates a new limit order. The side of the order (Ask/Bid) is derived form the given funds bucket.
@@ -66,40 +107,210 @@ blueprint! {
         /// a Bid order is inferred. The order is created with the given price. The price must always be (irrespective of the side)
         /// the amount of the quote resource that must be paid in order to obtain one unit of the base resource!
         ///
-        /// Returns a bucket with a NFR that represents the order. This NFR can be used to track the order
-        /// and to cancel it or redeem the traded resources.
+        /// The time in force determines how the order is executed:
+        /// - GoodTilCanceled: The part of the order that can be matched against existing limit orders at the given price or
+        ///   a better one is executed immediately. The remainder rests in the order book.
+        /// - GoodTilEpoch: Like GoodTilCanceled, but the resting order can no longer be matched after the given epoch.
+        ///   It can still be closed in order to redeem its funds.
+        /// - PostOnly: The order rests in the order book completely. It is rejected if any part of it would be matched immediately.
+        /// - ImmediateOrCancel: The part of the order that can be matched is executed immediately, the remainder is refunded.
+        /// - FillOrKill: The order is executed immediately and completely or not at all.
         ///
-        /// Panics if the order would be a market order. Never executes directly against existing market orders.
+        /// Holders of the fee discount badge can present a proof of it in order to pay reduced fees.
+        ///
+        /// Returns three buckets with
+        /// 1. A NFR that represents the part of the order that rests in the order book. This NFR can be used to track the order
+        ///    and to cancel it or redeem the traded resources. None if no part of the order rests in the order book.
+        /// 2. The funds that have not been spent and do not rest in the order book, e.g. the refund of an immediate-or-cancel order.
+        /// 3. The traded funds that have been received by executing the order immediately, less the taker fee.
+        ///    None if nothing has been traded.
+        ///
+        /// Panics if a post-only order would be matched immediately or if a fill-or-kill order cannot be filled completely.
         /// Panics if price is <= 0
-        pub fn new_limit_order(&mut self, funds: Bucket, price: Decimal) -> Bucket {
+        pub fn new_limit_order(
+            &mut self,
+            funds: Bucket,
+            price: Decimal,
+            time_in_force: TimeInForce,
+            fee_badge: Option<Proof>,
+        ) -> (Option<Bucket>, Bucket, Option<Bucket>) {
+            let discounted = self.is_fee_discounted(fee_badge);
+            self.place_limit_order(funds, price, time_in_force, discounted)
+        }
+
+        /// Creates a batch of limit orders from the given funds. Each order is given as a tuple of price and quantity,
+        /// where the quantity is taken out of the funds bucket. All orders are created with the given time in force and
+        /// are executed in the given order, just as if `new_limit_order` was called for each of them.
+        ///
+        /// Returns three buckets with
+        /// 1. The NFRs of all orders that rest in the order book
+        /// 2. The funds that have not been used by any order, including the unspent funds of the individual orders
+        /// 3. The traded funds that have been received by executing the orders immediately, less the taker fee.
+        ///    None if nothing has been traded.
+        ///
+        /// Panics if the funds do not suffice for all orders or if any of the orders is invalid.
+        pub fn new_limit_orders(
+            &mut self,
+            mut funds: Bucket,
+            orders: Vec<(Decimal, Decimal)>,
+            time_in_force: TimeInForce,
+            fee_badge: Option<Proof>,
+        ) -> (Bucket, Bucket, Option<Bucket>) {
+            let discounted = self.is_fee_discounted(fee_badge);
+            let total_quantity = orders
+                .iter()
+                .fold(Decimal::zero(), |total, (_, quantity)| total + *quantity);
+            assert!(
+                total_quantity <= funds.amount(),
+                "Insufficient funds: the orders require {} but only {} were supplied",
+                total_quantity,
+                funds.amount()
+            );
+
+            let mut order_buckets = Bucket::new(self.order_resource);
+            let mut traded_funds: Option<Bucket> = None;
+            for (price, quantity) in orders {
+                let (order_bucket, unspent_funds, traded) =
+                    self.place_limit_order(funds.take(quantity), price, time_in_force, discounted);
+
+                if let Some(order_bucket) = order_bucket {
+                    order_buckets.put(order_bucket);
+                }
+                funds.put(unspent_funds);
+                if let Some(traded) = traded {
+                    match traded_funds.as_mut() {
+                        Some(bucket) => bucket.put(traded),
+                        None => traded_funds = Some(traded),
+                    }
+                }
+            }
+
+            (order_buckets, funds, traded_funds)
+        }
+
+        /// Closes all limit orders in the given bucket, just as if `close_limit_order` was called for each of them.
+        /// As the orders may live on both sides of the order book, the refunds and the traded resources are aggregated
+        /// per resource. The two buckets contain
+        /// 1. all refunds and traded resources in the base resource
+        /// 2. all refunds and traded resources in the quote resource
+        pub fn close_limit_orders(&mut self, mut order_bucket: Bucket) -> (Bucket, Bucket) {
+            // Make sure the given bucket does indeed contain order NFRs
+            assert_eq!(
+                order_bucket.resource_address(),
+                self.order_resource,
+                "Invalid resource supplied: bucket does not contain an order"
+            );
+
+            let mut base_bucket = self.base_funds.take(Decimal::zero());
+            let mut quote_bucket = self.quote_funds.take(Decimal::zero());
+            for order_key in order_bucket.non_fungible_ids() {
+                let (refund, traded) = self.close_limit_order(order_bucket.take_non_fungible(&order_key));
+                for bucket in [refund, traded] {
+                    match self.get_order_side(&bucket) {
+                        Side::Ask => base_bucket.put(bucket),
+                        Side::Bid => quote_bucket.put(bucket),
+                    }
+                }
+            }
+
+            // All order NFRs have been taken out of the bucket and burned
+            self.minter.authorize(|| order_bucket.burn());
+
+            (base_bucket, quote_bucket)
+        }
+
+        /// Creates a new limit order for the given funds. See `new_limit_order` for details.
+        /// The fee discount has already been determined by the caller.
+        fn place_limit_order(
+            &mut self,
+            funds: Bucket,
+            price: Decimal,
+            time_in_force: TimeInForce,
+            discounted: bool,
+        ) -> (Option<Bucket>, Bucket, Option<Bucket>) {
             // Determine the side of the order
             let side = self.get_order_side(&funds);
+            let quantity = funds.amount();
             // Generate a new random order key
             let order_key = NonFungibleId::random();
             // Create a new limit order object. This will check that the price is not <= 0
-            let order = LimitOrder::new(order_key.clone(), side, price, funds.amount());
-            // Insert the limit order into the order book. This panics if the order would be a market order.
-            // The order book will only store a reference to the order (the order key). The order NFR will be given to the
-            // user at the end of this method.
-            self.order_book.insert_limit_order(&order);
+            let mut order = LimitOrder::new(order_key.clone(), side, price, quantity);
+            order.maker_fee = self.fee_schedule.maker_fee(discounted);
+            order.taker_fee = self.fee_schedule.taker_fee(discounted);
 
-            // Store the user supplied funds in the correct vault
-            match side {
-                Side::Ask => self.base_funds.put(funds),
-                Side::Bid => self.quote_funds.put(funds),
+            match time_in_force {
+                TimeInForce::PostOnly => {
+                    let best_limit_order = self.get_best_order(side.opposite());
+                    assert!(
+                        best_limit_order.map_or(true, |best| !side.crosses(price, best.price)),
+                        "Post-only order would be a market order"
+                    );
+                }
+                TimeInForce::GoodTilEpoch(expiry_epoch) => {
+                    assert!(
+                        expiry_epoch >= Runtime::current_epoch(),
+                        "Parameter expiry_epoch must not lie in the past"
+                    );
+                    order.expiry_epoch = Some(expiry_epoch);
+                }
+                _ => (),
             }
 
-            // Mint a new NFR representing the order and give it to the user
-            self.minter.authorize(|| {
-                borrow_resource_manager!(self.order_resource)
-                    .mint_non_fungible(&order_key, order)
-            })
+            // Store the user supplied funds in the correct vault and match them against existing limit orders
+            // up to the order's price
+            self.funds_vault(side).put(funds);
+            let result = self.match_orders(side, quantity, Some(price));
+            if let TimeInForce::FillOrKill = time_in_force {
+                assert!(
+                    !result.exhausted,
+                    "Fill-or-kill order cannot be filled completely"
+                );
+            }
+
+            // The remainder of the order rests in the order book unless the order is immediate-or-cancel.
+            // Only dust remains if matching has not been exhausted, so the order is not placed in that case.
+            let remaining_quantity = quantity - result.spent;
+            let rests = result.exhausted
+                && !matches!(
+                    time_in_force,
+                    TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill
+                );
+            let order_taker_fee = order.taker_fee;
+            let order_bucket = if rests {
+                // The order NFR only represents the part of the order that rests in the order book.
+                // The order book will only store a reference to the order (the order key).
+                order.quantity = remaining_quantity;
+                self.order_book.insert_limit_order(&order);
+
+                // Mint a new NFR representing the order
+                Some(self.minter.authorize(|| {
+                    borrow_resource_manager!(self.order_resource)
+                        .mint_non_fungible(&order_key, order)
+                }))
+            } else {
+                None
+            };
+
+            // Take the unspent funds and the traded funds out of the vaults
+            let unspent_funds = if rests {
+                self.funds_vault(side).take(Decimal::zero())
+            } else {
+                self.funds_vault(side).take(remaining_quantity)
+            };
+            let traded_funds = self.take_traded_funds(side.opposite(), result.received, order_taker_fee);
+
+            // Executing the order might have triggered stop orders
+            self.trigger_stop_orders();
+
+            (order_bucket, unspent_funds, traded_funds)
         }
 
         /// Closes the given limit order. If the order has not been filled completely, it is canceled, i.e. removed from the order book.
+        /// This method also closes stop orders. If a stop order has not been triggered yet, it is removed from the trigger book.
         /// The two buckets contain
         /// 1. the refund of the provided resource, in case the order has not been filled completely
-        /// 2. the traded/received resource, in case the order has been filled/filled partially
+        /// 2. the traded/received resource, in case the order has been filled/filled partially. The fees charged on
+        ///    the order are deducted from this amount.
         pub fn close_limit_order(&mut self, order_bucket: Bucket) -> (Bucket, Bucket) {
             // Make sure the given bucket does indeed contain an order NFR
             assert_eq!(
@@ -113,28 +324,163 @@ blueprint! {
 
             // If the order has not been filled completely, it still is referenced in the order book so we have to remove it.
             // If the order has already been filled completely, it will already have been removed from the order book.
-            if order.quantity_filled < order.quantity {
-                self.order_book.remove_limit_order(&order);
+            // Stop orders that have not been triggered yet must be removed from the trigger book instead.
+            match order.status {
+                OrderStatus::Dormant => self.stop_book.remove_stop_order(&order),
+                OrderStatus::Open => self.order_book.remove_limit_order(&order),
+                OrderStatus::Closed => (),
             }
             // Burn the order NFR. It is no longer needed as the order will no longer exist after this method finishes.
             self.minter
                 .authorize(|| order_bucket.burn());
 
-            // Calculate 1) the amount the user must be refunded if their order has not been filled completely
-            // and 2) the amount that the user has traded successfully if their order has been (at least partially) filled.
-            let (refund_amount, traded_amount) = order.calculate_close_amounts();
+            // Calculate 1) the amount the user must be refunded if their order has not been filled completely,
+            // 2) the amount that the user has traded successfully if their order has been (at least partially) filled
+            // and 3) the fees that are charged on the traded amount.
+            let (refund_amount, traded_amount, fee_amount) = order.calculate_close_amounts();
+
+            // Move the fees into the fee vault of the traded resource
+            let traded_side = order.side.opposite();
+            let fees = self.funds_vault(traded_side).take(fee_amount);
+            self.fees_vault(traded_side).put(fees);
 
             // Depending on the order side, take the refund and traded resources out of the correct vaults and give them to the user.
-            match order.side {
-                Side::Ask => (
-                    self.base_funds.take(refund_amount),
-                    self.quote_funds.take(traded_amount),
-                ),
-                Side::Bid => (
-                    self.quote_funds.take(refund_amount),
-                    self.base_funds.take(traded_amount),
-                ),
+            (
+                self.funds_vault(order.side).take(refund_amount),
+                self.funds_vault(traded_side).take(traded_amount),
+            )
+        }
+
+        /// Amends the given limit order without closing it. The order keeps its NFR and all funds that it has traded so far.
+        /// - If a new_quantity is given, the unfilled quantity of the order is reduced to that quantity. The order keeps
+        ///   its position in the queue of its price level.
+        /// - If a new_price is given, the order is moved to the back of the queue of the new price level. If the order
+        ///   can be matched at the new price, the matching part is executed immediately, just like with a new limit order.
+        ///   The traded funds can be redeemed by closing the order.
+        ///
+        /// Returns two buckets with
+        /// 1. The order NFR
+        /// 2. The refund of the quantity that the order has been reduced by
+        ///
+        /// Panics if the order does not rest in the order book or has expired.
+        /// Panics if new_quantity is <= 0 or larger than the unfilled quantity of the order.
+        /// Panics if new_price is <= 0
+        pub fn amend_limit_order(
+            &mut self,
+            order_bucket: Bucket,
+            new_quantity: Option<Decimal>,
+            new_price: Option<Decimal>,
+        ) -> (Bucket, Bucket) {
+            // Make sure the given bucket does indeed contain an order NFR
+            assert_eq!(
+                order_bucket.resource_address(),
+                self.order_resource,
+                "Invalid resource supplied: bucket does not contain an order"
+            );
+            // Load the data belonging to the order NFR
+            let mut order: LimitOrder =
+                order_bucket.non_fungible::<LimitOrder>().data();
+            let order_key = order.order_key.clone();
+            assert!(
+                order.status == OrderStatus::Open,
+                "Only orders that rest in the order book can be amended"
+            );
+            assert!(
+                !order.is_expired(Runtime::current_epoch()),
+                "Expired orders cannot be amended"
+            );
+
+            // Reduce the quantity of the order. The order book only references the order by its key,
+            // so the order keeps its position in the queue.
+            let mut refund_amount = Decimal::zero();
+            if let Some(new_quantity) = new_quantity {
+                assert!(new_quantity.is_positive(), "Parameter new_quantity must be > zero");
+                assert!(
+                    new_quantity <= order.unfilled_quantity(),
+                    "The quantity of an order can only be reduced"
+                );
+                refund_amount = order.unfilled_quantity() - new_quantity;
+                order.quantity -= refund_amount;
+                self.order_book.reduce_limit_order(&order, refund_amount);
             }
+
+            // Move the order to the new price level. The part of the order that crosses the spread at the new price
+            // is executed right away.
+            if let Some(new_price) = new_price {
+                assert!(new_price.is_positive(), "Parameter new_price must be > zero");
+                self.order_book.remove_limit_order(&order);
+                order.price = new_price;
+
+                let result = self.match_orders(order.side, order.unfilled_quantity(), Some(new_price));
+                order.fill_as_taker(result.spent, result.received);
+                if result.exhausted {
+                    self.order_book.insert_limit_order(&order);
+                } else {
+                    order.status = OrderStatus::Closed;
+                }
+            }
+
+            let refund = self.funds_vault(order.side).take(refund_amount);
+            self.update_order(&order_key, order);
+
+            // Moving the order might have triggered stop orders
+            self.trigger_stop_orders();
+
+            (order_bucket, refund)
+        }
+
+        /// Creates a new stop order. The side of the order (Ask/Bid) is derived form the given funds bucket in the same way
+        /// as for limit orders. The order sits dormant in a separate trigger book until a trade crosses the given trigger price.
+        /// Ask orders are triggered by trades at or below the trigger price (e.g. a stop-loss order), Bid orders are triggered by
+        /// trades at or above the trigger price.
+        ///
+        /// Once triggered, the order is converted into
+        /// - a limit order at the given limit_price, if a limit price has been specified (stop-limit order)
+        /// - a market order, if no limit price has been specified (stop-market order)
+        ///
+        /// Holders of the fee discount badge can present a proof of it in order to pay reduced fees.
+        ///
+        /// Returns a bucket with a NFR that represents the order. The NFR can be used to close the order via
+        /// `close_limit_order`, just like a regular limit order.
+        ///
+        /// Panics if the order would be triggered immediately by the last trade.
+        /// Panics if trigger_price or limit_price are <= 0
+        pub fn new_stop_order(
+            &mut self,
+            funds: Bucket,
+            trigger_price: Decimal,
+            limit_price: Option<Decimal>,
+            fee_badge: Option<Proof>,
+        ) -> Bucket {
+            // Determine the side of the order
+            let side = self.get_order_side(&funds);
+            // Generate a new random order key
+            let order_key = NonFungibleId::random();
+            // Create a new stop order object. This will check that the prices are not <= 0
+            let mut order = LimitOrder::new_stop(order_key.clone(), side, trigger_price, limit_price, funds.amount());
+            let discounted = self.is_fee_discounted(fee_badge);
+            order.maker_fee = self.fee_schedule.maker_fee(discounted);
+            order.taker_fee = self.fee_schedule.taker_fee(discounted);
+
+            // A stop order that would be triggered right away is most likely a mistake
+            if let Some(last_price) = self.last_price {
+                assert!(
+                    !StopBook::is_triggered(side, trigger_price, last_price),
+                    "Stop order would be triggered immediately"
+                );
+            }
+
+            // Insert the stop order into the trigger book. Just like the order book, the trigger book only holds the order key.
+            self.stop_book.insert_stop_order(&order);
+
+            // Store the user supplied funds in the correct vault
+            self.funds_vault(side).put(funds);
+
+            // Mint a new NFR representing the order and give it to the user
+            self.minter.authorize(|| {
+                borrow_resource_manager!(self.order_resource)
+                    .mint_non_fungible(&order_key, order)
+            })
         }
 
         /// Creates a new market order that is executed directly against existing limit orders.
@@ -146,105 +492,439 @@ blueprint! {
         /// some minimum amount of the traded resource is received. This is especially important if the user places a
         /// large order and "rides down" the order book multiple price levels.
         ///
+        /// After the market order has been executed, all stop orders that are triggered by the resulting trades are executed.
+        ///
+        /// The taker fee is deducted from the traded funds. Holders of the fee discount badge can present a proof of it
+        /// in order to pay a reduced fee.
+        ///
         /// Returns two buckets with
         /// 1. Any dust that might still exist in the supplied funds bucket after executing the market order.
-        /// 2. The traded funds that are received in exchange for the supplied funds.
+        /// 2. The traded funds that are received in exchange for the supplied funds, less the taker fee.
         ///
         /// Panics if the order cannot be filled by existing limit orders.
-        pub fn new_market_order(&mut self, mut funds: Bucket) -> (Bucket, Option<Bucket>) {
+        pub fn new_market_order(&mut self, funds: Bucket, fee_badge: Option<Proof>) -> (Bucket, Option<Bucket>) {
             // Infer the side of the order
-            let market_order_side = self.get_order_side(&funds);
-            let limit_order_side = market_order_side.opposite();
+            let side = self.get_order_side(&funds);
+            let quantity = funds.amount();
+            let taker_fee = self.fee_schedule.taker_fee(self.is_fee_discounted(fee_badge));
 
-            // Create a bucket for accumulating all traded funds
-            // (funds the user will receive from limit orders that they have filled)
-            let mut funds_to_return: Option<Bucket> = None;
+            // Store the market order funds in this component. The limit orders that are filled will be
+            // able to claim them later. The unspent funds are taken out again below.
+            self.funds_vault(side).put(funds);
 
-            // Enter into a loop of always loading the best limit order from the order book and
-            // filling it. Stop when the funds of the market order are expended.
+            // Match the market order against the limit orders on the opposite side of the order book.
             // This may fail in a low liquidity situation where there are too few funds on the limit
             // order side to fill the market order.
-            let mut last_price = Decimal(1i128);
-            while !is_almost_zero(funds.amount(), last_price) {
-                // Get the current best limit order from the order book (this only returns the order key).
-                // Panic if there are no more limit orders that can be matched to the market order
-                let limit_order_key = self.order_book.get_best_order(limit_order_side)
-                    .expect("Insufficient liquidity: no limit orders found that can be matched to the market order")
-                    .clone();
-
-                // Using the order key, load the data for the limit order
-                let mut limit_order: LimitOrder =
-                    borrow_resource_manager!(self.order_resource).get_non_fungible_data(&limit_order_key);
+            let result = self.match_orders(side, quantity, None);
+            assert!(
+                !result.exhausted,
+                "Insufficient liquidity: no limit orders found that can be matched to the market order"
+            );
 
-                // Save the limit order's price as the last known price
-                last_price = limit_order.price;
+            self.settle_market_order(side, quantity, result, taker_fee)
+        }
 
-                // Calculate what quantity of the limit order can be filled using the funds that remain in the market order
-                let supplied_quantity = match limit_order_side {
-                    Side::Ask => funds.amount() / limit_order.price,
-                    Side::Bid => funds.amount() * limit_order.price,
-                };
+        /// Creates a new market order whose slippage is bounded. Like `new_market_order`, the order is executed directly
+        /// against existing limit orders and the side of the order is derived from the given funds bucket.
+        ///
+        /// If a worst_price is given, only limit orders at that price or a better one are matched. Matching stops at the
+        /// first limit order with a worse price and the unspent funds are returned instead of filling at that price.
+        /// In contrast to `new_market_order`, the order does not fail if it cannot be filled completely.
+        /// If a min_received amount is given, the order fails if less than that amount would be received (after fees).
+        ///
+        /// Returns two buckets with
+        /// 1. The funds that have not been spent, either because the worst price has been reached or because liquidity has run out.
+        /// 2. The traded funds that are received in exchange for the spent funds, less the taker fee.
+        ///
+        /// Panics if less than min_received would be received.
+        /// Panics if worst_price is <= 0
+        pub fn new_bounded_market_order(
+            &mut self,
+            funds: Bucket,
+            worst_price: Option<Decimal>,
+            min_received: Option<Decimal>,
+            fee_badge: Option<Proof>,
+        ) -> (Bucket, Option<Bucket>) {
+            assert!(
+                worst_price.map_or(true, |price| price.is_positive()),
+                "Parameter worst_price must be > zero"
+            );
 
-                // Also calculate the quantity of the limit order that has not been filled yet
-                let unfilled_quantity = limit_order.quantity - limit_order.quantity_filled;
+            // Infer the side of the order
+            let side = self.get_order_side(&funds);
+            let quantity = funds.amount();
+            let taker_fee = self.fee_schedule.taker_fee(self.is_fee_discounted(fee_badge));
 
-                // The fill quantity for this loop pass. This is the minimum of the quantity supplied via the market order
-                // and the unfilled quantity remaining in the limit order
-                let fill_quantity = min(supplied_quantity, unfilled_quantity);
+            // Match the market order against the limit orders up to the worst acceptable price
+            self.funds_vault(side).put(funds);
+            let result = self.match_orders(side, quantity, worst_price);
+            let (unspent_funds, funds_to_return) = self.settle_market_order(side, quantity, result, taker_fee);
 
-                // Then, fill the limit order wit this quantity
-                limit_order.fill(fill_quantity);
+            if let Some(min_received) = min_received {
+                let received = funds_to_return
+                    .as_ref()
+                    .map_or(Decimal::zero(), |bucket| bucket.amount());
+                assert!(
+                    received >= min_received,
+                    "Slippage limit exceeded: the order would only receive {} instead of at least {}",
+                    received,
+                    min_received
+                );
+            }
+
+            (unspent_funds, funds_to_return)
+        }
+
+        /// Quotes a market order for the given amount of the given resource without executing it. If the given resource is
+        /// the base resource, an Ask order is quoted, if it is the quote resource, a Bid order is quoted.
+        /// If a worst_price is given, only limit orders at that price or a better one are considered, just like with
+        /// `new_bounded_market_order`. Whether the fee discount applies can be specified via fee_discounted.
+        ///
+        /// The quote is based on the current state of the order book. Stop orders that the market order would trigger
+        /// are not taken into account.
+        ///
+        /// Returns a MarketOrderQuote with the expected fill and average price.
+        pub fn quote_market_order(
+            &self,
+            resource_address: ResourceAddress,
+            amount: Decimal,
+            worst_price: Option<Decimal>,
+            fee_discounted: bool,
+        ) -> MarketOrderQuote {
+            let side = self.get_side(resource_address);
+            let current_epoch = Runtime::current_epoch();
 
-                // Depending on the limit order side, take an appropriate amount of the market order funds and store them in this component.
-                // The market maker user will be able to claim them later. Also add the traded funds that the taker user will receive to the
-                // funds_to_return bucket.
-                
-                let bucket_to_add = match limit_order_side {
-                    Side::Ask => {
-                        self.quote_funds
-                            .put(funds.take(fill_quantity * limit_order.price));
-                        self.base_funds.take(fill_quantity)
+            // Walk the order book in the same way as match_orders does, without filling any limit orders
+            let mut spent = Decimal::zero();
+            let mut received = Decimal::zero();
+            let mut last_price = worst_price.unwrap_or(Decimal(1i128));
+            let mut last_fill_price = None;
+            for limit_order_key in self.order_book.iter_orders(side.opposite()) {
+                if is_almost_zero(amount - spent, last_price) {
+                    break;
+                }
+
+                let limit_order = self.get_order(limit_order_key);
+                if limit_order.is_expired(current_epoch) {
+                    continue;
+                }
+                if let Some(worst_price) = worst_price {
+                    if !side.crosses(worst_price, limit_order.price) {
+                        break;
                     }
-                    Side::Bid => {
-                        self.base_funds
-                            .put(funds.take(fill_quantity / limit_order.price));
-                        self.quote_funds.take(fill_quantity)
+                }
+
+                last_price = limit_order.price;
+                let (fill_quantity, spent_quantity) = calculate_fill(side, amount - spent, &limit_order);
+                spent += spent_quantity;
+                received += fill_quantity;
+                last_fill_price = Some(limit_order.price);
+            }
+
+            // The price is always expressed in the quote resource per unit of the base resource
+            let (base_amount, quote_amount) = match side {
+                Side::Ask => (spent, received),
+                Side::Bid => (received, spent),
+            };
+            let fee = received * self.fee_schedule.taker_fee(fee_discounted);
+
+            MarketOrderQuote {
+                spent,
+                received: received - fee,
+                fee,
+                average_price: if base_amount.is_positive() {
+                    Some(quote_amount / base_amount)
+                } else {
+                    None
+                },
+                worst_price: last_fill_price,
+                filled_completely: is_almost_zero(amount - spent, last_price),
+            }
+        }
+
+        /// Returns a snapshot of the order book with up to the given number of price levels per side, best price levels first.
+        /// Each price level holds the aggregated unfilled quantity of all orders on it.
+        /// Expired orders are included until they are encountered by a market order.
+        pub fn get_depth(&self, levels: u32) -> OrderBookDepth {
+            self.order_book.get_depth(levels as usize)
+        }
+
+        /// Returns the best bid and ask prices, the spread and the mid price of the order book as well as the price
+        /// of the last trade.
+        pub fn get_ticker(&self) -> Ticker {
+            let best_bid = self.order_book.get_best_price(Side::Bid);
+            let best_ask = self.order_book.get_best_price(Side::Ask);
+            let (spread, mid_price) = match (best_bid, best_ask) {
+                (Some(bid), Some(ask)) => (Some(ask - bid), Some((bid + ask) / Decimal::from(2))),
+                _ => (None, None),
+            };
+
+            Ticker {
+                best_bid,
+                best_ask,
+                spread,
+                mid_price,
+                last_price: self.last_price,
+            }
+        }
+
+        /// Returns up to limit trades, starting with the trade with the given sequence number. Oldest trades come first.
+        /// The total number of trades can be retrieved via `get_trade_count`.
+        pub fn get_trades(&self, offset: u64, limit: u32) -> Vec<Trade> {
+            self.history.get_trades(offset, limit)
+        }
+
+        /// Returns the number of trades that have happened on this trading pair
+        pub fn get_trade_count(&self) -> u64 {
+            self.history.trade_count()
+        }
+
+        /// Returns up to limit OHLCV candles, starting with the candle with the given sequence number. Oldest candles come first.
+        /// Intervals without any trades do not have a candle. The total number of candles can be retrieved via `get_candle_count`.
+        pub fn get_candles(&self, offset: u64, limit: u32) -> Vec<Candle> {
+            self.history.get_candles(offset, limit)
+        }
+
+        /// Returns the number of OHLCV candles that have been recorded for this trading pair
+        pub fn get_candle_count(&self) -> u64 {
+            self.history.candle_count()
+        }
+
+        /// Takes the unspent funds and the traded funds of an executed market order out of the vaults and executes all
+        /// stop orders that have been triggered by it.
+        fn settle_market_order(
+            &mut self,
+            side: Side,
+            quantity: Decimal,
+            result: MatchResult,
+            taker_fee: Decimal,
+        ) -> (Bucket, Option<Bucket>) {
+            // Take 1) the unspent funds of the market order and 2) the traded funds coming from the limit order(s)
+            // out of the vaults
+            let unspent_funds = self.funds_vault(side).take(quantity - result.spent);
+            let funds_to_return = self.take_traded_funds(side.opposite(), result.received, taker_fee);
+
+            // The market order might have moved the price so far that stop orders have been triggered
+            self.trigger_stop_orders();
+
+            // Finally return both buckets to the user
+            (unspent_funds, funds_to_return)
+        }
+
+        /// Matches an order of the given side and quantity against the limit orders on the opposite side of the order book.
+        /// If a limit price is given, only limit orders with that price or a better one are matched.
+        /// The funds of the matched order must already have been deposited into the correct vault. The limit orders
+        /// that are filled are updated so that their owners can claim the traded funds later.
+        ///
+        /// Returns a MatchResult with the quantity that has been spent and the amount that has been received in return.
+        fn match_orders(
+            &mut self,
+            side: Side,
+            quantity: Decimal,
+            limit_price: Option<Decimal>,
+        ) -> MatchResult {
+            let limit_order_side = side.opposite();
+            let current_epoch = Runtime::current_epoch();
+            let mut result = MatchResult {
+                spent: Decimal::zero(),
+                received: Decimal::zero(),
+                exhausted: false,
+            };
+
+            // Enter into a loop of always loading the best limit order from the order book and
+            // filling it. Stop when the quantity is spent or no more limit orders can be matched.
+            let mut last_price = limit_price.unwrap_or(Decimal(1i128));
+            while !is_almost_zero(quantity - result.spent, last_price) {
+                // Get the current best limit order from the order book
+                let mut limit_order = match self.get_best_order(limit_order_side) {
+                    Some(limit_order) => limit_order,
+                    None => {
+                        result.exhausted = true;
+                        break;
                     }
                 };
+                let limit_order_key = limit_order.order_key.clone();
 
-                match funds_to_return.as_mut() {
-                    Some(bucket) => bucket.put(bucket_to_add),
-                    None => funds_to_return = Some(bucket_to_add)
+                // Stop if the limit order's price is worse than the limit price
+                if let Some(limit_price) = limit_price {
+                    if !side.crosses(limit_price, limit_order.price) {
+                        result.exhausted = true;
+                        break;
+                    }
                 }
 
+                // Save the limit order's price as the last known price
+                last_price = limit_order.price;
+
+                // Calculate what quantity of the limit order can be filled using the quantity that remains
+                // and how much of that remaining quantity is spent on it
+                let (fill_quantity, spent_quantity) =
+                    calculate_fill(side, quantity - result.spent, &limit_order);
+
+                // Then, fill the limit order wit this quantity
+                limit_order.fill(fill_quantity);
+                self.order_book.reduce_limit_order(&limit_order, fill_quantity);
+
+                // Record how much has been spent and received. The funds themselves stay in the vaults, where
+                // the limit order's owner will be able to claim them later.
+                result.spent += spent_quantity;
+                result.received += fill_quantity;
+                self.last_price = Some(limit_order.price);
+
+                // Record the trade in the trade history
+                self.history.record_trade(Trade {
+                    price: limit_order.price,
+                    quantity: match side {
+                        Side::Ask => spent_quantity,
+                        Side::Bid => fill_quantity,
+                    },
+                    taker_side: side,
+                    epoch: current_epoch,
+                });
+
                 // Check if the limit order has been filled completely.
                 // If so, remove it from the order book.
                 // The limit order NFR representing the limit order remains in the user's possession.
                 if limit_order.quantity_filled == limit_order.quantity {
                     self.order_book.remove_limit_order(&limit_order);
+                    limit_order.status = OrderStatus::Closed;
                 }
 
                 // Update the data of the limit order NFR
-                self.minter.authorize(|| {
-                    borrow_resource_manager!(self.order_resource).update_non_fungible_data(
-                        &limit_order_key,
-                        limit_order
-                    )
-                });
+                self.update_order(&limit_order_key, limit_order);
             }
 
-            // Finally return to the user 1) the unspent funds of the market order
-            // and 2) the traded funds coming from the limit order(s)
-            (funds, funds_to_return)
+            result
+        }
+
+        /// Withdraws all fees that have been collected by this trading pair.
+        /// This method can only be called by the holder of the admin badge.
+        ///
+        /// Returns two buckets with the fees collected in 1) the base resource and 2) the quote resource.
+        pub fn withdraw_fees(&mut self) -> (Bucket, Bucket) {
+            (self.base_fees.take_all(), self.quote_fees.take_all())
+        }
+
+        /// Loads the best limit order on the given side of the order book. Returns None if no order exists.
+        /// Expired orders that are encountered on the way are removed from the order book and closed,
+        /// so that their owners can redeem their funds.
+        fn get_best_order(&mut self, side: Side) -> Option<LimitOrder> {
+            let current_epoch = Runtime::current_epoch();
+            loop {
+                let order_key = self.order_book.get_best_order(side)?.clone();
+                let mut order = self.get_order(&order_key);
+                if !order.is_expired(current_epoch) {
+                    return Some(order);
+                }
+
+                self.order_book.remove_limit_order(&order);
+                order.status = OrderStatus::Closed;
+                self.update_order(&order_key, order);
+            }
+        }
+
+        /// Executes all stop orders that are triggered by the last trade price.
+        /// As executing a stop order may move the price further, this is repeated until no more stop orders are triggered.
+        fn trigger_stop_orders(&mut self) {
+            while let Some(last_price) = self.last_price {
+                let order_key = match self.stop_book.get_triggered_order(last_price) {
+                    Some(order_key) => order_key.clone(),
+                    None => break,
+                };
+                let mut order = self.get_order(&order_key);
+                self.stop_book.remove_stop_order(&order);
+
+                // Execute the order against the order book. A stop limit order only matches up to its limit price.
+                // The funds of the order have been deposited when the order was created.
+                let limit_price = match order.order_type {
+                    OrderType::StopLimit { .. } => Some(order.price),
+                    _ => None,
+                };
+                let result = self.match_orders(order.side, order.unfilled_quantity(), limit_price);
+                order.fill_as_taker(result.spent, result.received);
+
+                // A stop limit order rests in the order book with its remaining quantity, unless only dust remains.
+                // Any other order is closed and its remaining funds can be redeemed by closing it.
+                order.status = if limit_price.is_some() && result.exhausted {
+                    self.order_book.insert_limit_order(&order);
+                    OrderStatus::Open
+                } else {
+                    OrderStatus::Closed
+                };
+
+                self.update_order(&order_key, order);
+            }
+        }
+
+        /// Loads the data of the order with the given key
+        fn get_order(&self, order_key: &NonFungibleId) -> LimitOrder {
+            borrow_resource_manager!(self.order_resource).get_non_fungible_data(order_key)
+        }
+
+        /// Updates the data of the order NFR with the given key
+        fn update_order(&self, order_key: &NonFungibleId, order: LimitOrder) {
+            self.minter.authorize(|| {
+                borrow_resource_manager!(self.order_resource).update_non_fungible_data(order_key, order)
+            });
+        }
+
+        /// Takes the given traded amount out of the funds vault of the given side. The taker fee is deducted from
+        /// that amount and put into the fee vault. Returns None if nothing has been traded.
+        fn take_traded_funds(&mut self, side: Side, traded_amount: Decimal, taker_fee: Decimal) -> Option<Bucket> {
+            if !traded_amount.is_positive() {
+                return None;
+            }
+
+            let mut traded_funds = self.funds_vault(side).take(traded_amount);
+            self.fees_vault(side).put(traded_funds.take(traded_amount * taker_fee));
+            Some(traded_funds)
+        }
+
+        /// Checks whether the given proof entitles its owner to a fee discount.
+        /// Panics if a proof is given that does not contain the discount badge.
+        fn is_fee_discounted(&self, fee_badge: Option<Proof>) -> bool {
+            match fee_badge {
+                Some(proof) => {
+                    assert!(
+                        self.fee_schedule.discount_badge == Some(proof.resource_address())
+                            && proof.amount() > Decimal::zero(),
+                        "Invalid fee discount badge"
+                    );
+                    proof.drop();
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Returns the vault that holds the fees collected in the resource provided by orders of the given side
+        fn fees_vault(&mut self, side: Side) -> &mut Vault {
+            match side {
+                Side::Ask => &mut self.base_fees,
+                Side::Bid => &mut self.quote_fees,
+            }
+        }
+
+        /// Returns the vault that holds the funds provided by orders of the given side
+        fn funds_vault(&mut self, side: Side) -> &mut Vault {
+            match side {
+                Side::Ask => &mut self.base_funds,
+                Side::Bid => &mut self.quote_funds,
+            }
         }
 
         /// Infers the side of the order from the resource contained in the given bucket.
         /// If the bucket contains the base resource Ask is inferred.
         /// If the bucket contains the quote resource Bid is inferred.
         fn get_order_side(&self, bucket: &Bucket) -> Side {
-            if bucket.resource_address() == self.base_funds.resource_address() {
+            self.get_side(bucket.resource_address())
+        }
+
+        /// Infers the side of an order that provides the given resource.
+        fn get_side(&self, resource_address: ResourceAddress) -> Side {
+            if resource_address == self.base_funds.resource_address() {
                 Side::Ask
-            } else if bucket.resource_address() == self.quote_funds.resource_address() {
+            } else if resource_address == self.quote_funds.resource_address() {
                 Side::Bid
             } else {
                 panic!("The supplied bucket contains an invalid resource")
@@ -253,6 +933,78 @@ blueprint! {
     }
 }
 
+/// Represents the quote for a market order
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Debug)]
+pub struct MarketOrderQuote {
+    /// The amount of the supplied resource that would be spent
+    pub spent: Decimal,
+
+    /// The amount of the opposite resource that would be received, less the taker fee
+    pub received: Decimal,
+
+    /// The taker fee that would be charged
+    pub fee: Decimal,
+
+    /// The average price at which the order would be filled. None if nothing would be filled.
+    pub average_price: Option<Decimal>,
+
+    /// The price of the worst limit order that would be filled. None if nothing would be filled.
+    pub worst_price: Option<Decimal>,
+
+    /// Whether the supplied amount would be spent completely
+    pub filled_completely: bool,
+}
+
+/// Represents the top of the order book of a trading pair
+#[derive(sbor::TypeId, sbor::Encode, sbor::Decode, sbor::Describe, Debug)]
+pub struct Ticker {
+    /// The price of the best Bid order. None if there are no Bid orders.
+    pub best_bid: Option<Decimal>,
+
+    /// The price of the best Ask order. None if there are no Ask orders.
+    pub best_ask: Option<Decimal>,
+
+    /// The difference between the best Ask and the best Bid price. None if one side of the order book is empty.
+    pub spread: Option<Decimal>,
+
+    /// The price halfway between the best Bid and the best Ask price. None if one side of the order book is empty.
+    pub mid_price: Option<Decimal>,
+
+    /// The price of the last trade. None if no trade has happened yet.
+    pub last_price: Option<Decimal>,
+}
+
+/// The result of matching an order against the limit orders in the order book
+struct MatchResult {
+    /// The quantity of the order that has been spent
+    spent: Decimal,
+
+    /// The amount of the opposite resource that has been received in exchange for the spent quantity
+    received: Decimal,
+
+    /// Whether matching has stopped because no more limit orders could be matched, although some quantity remained
+    exhausted: bool,
+}
+
+/// Calculates how an order of the given side fills the given limit order with the quantity that remains in the order.
+/// Returns 1) the quantity of the limit order that is filled and 2) the quantity of the order that is spent on it.
+fn calculate_fill(side: Side, remaining_quantity: Decimal, limit_order: &LimitOrder) -> (Decimal, Decimal) {
+    // The quantity of the limit order that could be filled using the remaining quantity
+    let supplied_quantity = match side {
+        Side::Ask => remaining_quantity * limit_order.price,
+        Side::Bid => remaining_quantity / limit_order.price,
+    };
+
+    // The fill quantity is the minimum of the supplied quantity and the unfilled quantity remaining in the limit order
+    let fill_quantity = min(supplied_quantity, limit_order.unfilled_quantity());
+    let spent_quantity = match side {
+        Side::Ask => fill_quantity / limit_order.price,
+        Side::Bid => fill_quantity * limit_order.price,
+    };
+
+    (fill_quantity, spent_quantity)
+}
+
 fn assert_is_fungible(resource: &ResourceAddress) {
     match borrow_resource_manager!(*resource).resource_type() {
         ResourceType::Fungible { .. } => (), // OK
@@ -284,6 +1036,31 @@ fn is_almost_zero(amount: Decimal, price: Decimal) -> bool {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_calculate_fill() {
+        let ask = LimitOrder::new(
+            NonFungibleId::from_str("1234").unwrap(),
+            Side::Ask,
+            10.into(),
+            100.into(),
+        );
+        // A bid order spending 500 quote fills 50 base of the ask order
+        assert_eq!(calculate_fill(Side::Bid, 500.into(), &ask), (50.into(), 500.into()));
+        // A bid order spending 5000 quote fills the ask order completely and only spends 1000 quote
+        assert_eq!(calculate_fill(Side::Bid, 5000.into(), &ask), (100.into(), 1000.into()));
+
+        let bid = LimitOrder::new(
+            NonFungibleId::from_str("5678").unwrap(),
+            Side::Bid,
+            10.into(),
+            100.into(),
+        );
+        // An ask order spending 5 base fills 50 quote of the bid order
+        assert_eq!(calculate_fill(Side::Ask, 5.into(), &bid), (50.into(), 5.into()));
+        // An ask order spending 50 base fills the bid order completely and only spends 10 base
+        assert_eq!(calculate_fill(Side::Ask, 50.into(), &bid), (100.into(), 10.into()));
+    }
+
     #[test]
     fn test_is_almost_zero() {
         // price, almost_zero, not_almost_zero
@@ -0,0 +1,12 @@
+use scrypto::prelude::*;
+
+use bonding_macros::blueprint_stub; // proc macro generating a "hidden" empty blueprint so the stubs can be used
+
+// Interface a Component has to expose to receive a flashswap from CandyDex.
+// "on_flashswap" gets the borrowed bucket along with the resource address the loan has to be repaid in
+// and must return a bucket of that resource covering loan amount plus protocol fee. Any surplus is
+// handed back to flashswap caller.
+#[blueprint_stub]
+pub trait FlashswapReceiver {
+    fn on_flashswap(&self, loan: Bucket, repay_address: ResourceAddress) -> Bucket;
+}
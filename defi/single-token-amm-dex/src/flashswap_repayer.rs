@@ -0,0 +1,39 @@
+use scrypto::prelude::*;
+
+// Minimal FlashswapReceiver implementation returning borrowed bucket along with all tokens previously
+// deposited. Useful to test flashswaps and as a template for own arbitrage Components.
+blueprint! {
+    struct FlashswapRepayer {
+        // Vault holding tokens used to pay flashswap fee, or the whole loan if repaid in another token.
+        funds: Vault,
+        // Hashmap with vaults keeping loans repaid in another token.
+        loans: HashMap<ResourceAddress, Vault>
+    }
+
+    impl FlashswapRepayer {
+        pub fn new(funds: Bucket) -> ComponentAddress {
+            Self {
+                funds: Vault::with_bucket(funds),
+                loans: HashMap::new()
+            }
+            .instantiate()
+            .globalize()
+        }
+
+        // can't do trait impl's within the blueprint, see FlashswapReceiver for the interface.
+        pub fn on_flashswap(&mut self, loan: Bucket, repay_address: ResourceAddress) -> Bucket {
+            assert!(self.funds.resource_address() == repay_address, " No funds to pay fee ");
+
+            let loan_address = loan.resource_address();
+            if loan_address == repay_address {
+                self.funds.put(loan);
+            } else {
+                match self.loans.get_mut(&loan_address) {
+                    Some(vault) => vault.put(loan),
+                    None => { self.loans.insert(loan_address, Vault::with_bucket(loan)); }
+                }
+            }
+            self.funds.take_all()
+        }
+    }
+}
@@ -1,6 +1,11 @@
+mod flashswap_receiver;
+mod flashswap_repayer;
+
 use sbor::*;
 use scrypto::prelude::*;
 
+use flashswap_receiver::FlashswapReceiverClient;
+
 blueprint! {
     struct CandyDex {        
         // XRD vault.
@@ -14,8 +19,14 @@ blueprint! {
         badge_map: HashMap<ResourceAddress, (Decimal, Decimal, ResourceAddress)>,         
         // Candy Hashmap with accrued fee, metaCandy amount & address.
         meta_map: HashMap<ResourceAddress, (Decimal, Decimal, ResourceAddress)>,         
+        // Candy Hashmap with accrued $XRD fee from flashswaps of the candy repaid in $XRD.
+        xrd_fee_map: HashMap<ResourceAddress, Decimal>,
+        // metaBadge Hashmap with accrued $XRD fee level at entry.
+        badge_xrd_map: HashMap<ResourceAddress, Decimal>,
         // metaCandy Hashmap with MetaToken resource adresses. 
         meta: HashMap<ResourceAddress, MetaToken>,  
+        // Candy Hashmap with cumulative price oracle.
+        oracle_map: HashMap<ResourceAddress, PriceOracle>,
         
         // Badge to mint and burn metaCandies.                      
         minter_badge: Vault,         
@@ -48,7 +59,10 @@ blueprint! {
                 candy_map: HashMap::new(),
                 badge_map: HashMap::new(),
                 meta_map: HashMap::new(),
+                xrd_fee_map: HashMap::new(),
+                badge_xrd_map: HashMap::new(),
                 meta: HashMap::new(),
+                oracle_map: HashMap::new(),
                 minter_badge: Vault::with_bucket(minter_badge),
                 owner_badge: badge_bucket.resource_address(),
                 xrd_fee: Decimal::zero(),
@@ -82,6 +96,7 @@ blueprint! {
                         .no_initial_supply();
                              
                 self.badge_map.insert(meta_badge_res_def,(entry_fee, meta_amnt, candy_addr));
+                self.badge_xrd_map.insert(meta_badge_res_def, CandyDex::xrd_fee_level(self, candy_addr));
 
                 self.minter_badge.authorize(|| { borrow_resource_manager!(meta_badge_res_def).mint(1) })
             }
@@ -109,6 +124,7 @@ blueprint! {
                   
                     if m_candy_amnt == Decimal::zero() {
                         self.badge_map.remove(&meta_badge_addr);
+                        self.badge_xrd_map.remove(&meta_badge_addr);
                         CandyDex::badge_burn(self, meta_badge);
                         Bucket::new(RADIX_TOKEN)
                     } else { 
@@ -188,6 +204,20 @@ blueprint! {
                 price
             }
 
+            // Set new candy price and accumulate previous one in candy price oracle.
+            fn set_price(&mut self, candy_addr: ResourceAddress, new_price: Decimal) {
+                match self.candy_map.get_mut(&candy_addr) {
+                    Some((_a,_b,price)) => *price = new_price,
+                    None => std::process::abort()                  
+                };
+
+                let epoch = Runtime::current_epoch();
+                self.oracle_map
+                    .entry(candy_addr)
+                    .or_insert(PriceOracle::new(epoch, new_price))
+                    .update(epoch, new_price);
+            }
+
             // Put candy to sell in vault whenever end user specify an exact number of candy to sell 
             // using swap functions.
             fn candyput_pri(
@@ -258,6 +288,59 @@ blueprint! {
                 }
             }
 
+            // Increment total accrued fee of a candy in relative hashmap, raising metaCandy value.
+            fn accrue_fee(&mut self, candy_addr: ResourceAddress, fee_amnt: Decimal) {
+                match self.meta_map.get_mut(&candy_addr) {
+                    Some((amnt_fee,_b,_c)) => *amnt_fee = *amnt_fee+fee_amnt,
+                    None => std::process::abort()                  
+                };
+            }
+
+            // Increment total accrued $XRD fee of a candy, claimable in $XRD by its liquidity providers.
+            fn accrue_xrd_fee(&mut self, candy_addr: ResourceAddress, fee_amnt: Decimal) {
+                let xrd_fee = self.xrd_fee_map.entry(candy_addr).or_insert(Decimal::zero());
+                *xrd_fee = *xrd_fee+fee_amnt;
+            }
+
+            // Retrieve total accrued $XRD fee of a candy.
+            fn xrd_fee_level(&self, candy_addr: ResourceAddress) -> Decimal {
+                *self.xrd_fee_map.get(&candy_addr).unwrap_or(&Decimal::zero())
+            }
+
+            // Calculate $XRD fee earned by a metaCandy amount since metaBadge entry.
+            fn xrd_fee_earned(
+                &self, 
+                meta_badge_addr: ResourceAddress, 
+                candy_addr: ResourceAddress, 
+                meta_amnt: Decimal, 
+                total_minted: Decimal
+            ) -> Decimal {
+                let entry_xrd_fee = *self.badge_xrd_map.get(&meta_badge_addr).unwrap_or(&Decimal::zero());
+                (CandyDex::xrd_fee_level(self, candy_addr)-entry_xrd_fee)*meta_amnt/total_minted
+            }
+
+            // Abort swap once deadline epoch is passed.
+            fn check_deadline(deadline: u64) {
+                let epoch = Runtime::current_epoch();
+                assert!( epoch <= deadline, " Swap deadline expired at epoch {}, current epoch is {} ", deadline, epoch);
+            }
+
+            // Abort swap whenever output amount falls below minimum accepted amount.
+            fn check_min_out(amount_out: Decimal, min_out: Decimal) {
+                assert!( 
+                    amount_out >= min_out, 
+                    " Output amount {} below minimum {}, slippage exceeded! ", amount_out, min_out
+                );
+            }
+
+            // Abort swap whenever input amount exceeds maximum accepted amount.
+            fn check_max_in(amount_in: Decimal, max_in: Decimal) {
+                assert!( 
+                    amount_in <= max_in, 
+                    " Input amount {} above maximum {}, slippage exceeded! ", amount_in, max_in
+                );
+            }
+
             // Calculate new candy price.
             fn price_mod(
                 &mut self, 
@@ -302,10 +385,7 @@ blueprint! {
                         }
                         let price_new = 
                             CandyDex::price_mod(self, amount, addr_pri, price_in, dec!(1));
-                        let (name,symbol,_price) = self.candy_map.get(&addr_pri).unwrap();
-                        let name_str = name.to_string();
-                        let symbol_str = symbol.to_string();
-                        self.candy_map.insert(addr_pri,(name_str,symbol_str,price_new));
+                        CandyDex::set_price(self, addr_pri, price_new);
                         amnt_pri/price_new
                     }
                 }
@@ -365,6 +445,7 @@ blueprint! {
             
             self.candy_map.insert(candy_addr,(name.clone(),symbol.clone(),new_price));
             self.meta_map.insert(candy_addr,(none,none,candy_addr));
+            self.oracle_map.insert(candy_addr,PriceOracle::new(Runtime::current_epoch(), new_price));
             
             CandyDex::add_meta_candy(self, name.clone(), symbol.clone(), candy_addr);
             
@@ -446,6 +527,9 @@ blueprint! {
             let candy_bucket: Bucket;
             let xrd_out: Bucket;
             let delta_fee: Decimal = *accrued_fee-(*entry_fee);
+            let xrd_fee_out: Decimal = CandyDex::xrd_fee_earned(
+                self, meta_badge.resource_address(), candy_addr, meta_candy_amnt, *total_minted
+            );
             
             candy_out_nbr = meta_candy_amnt+delta_fee*meta_candy_amnt/(*total_minted);
             
@@ -465,8 +549,7 @@ blueprint! {
                             std::process::abort()
                         }
                     };
-                    let zero: Decimal = dec!(0);
-                    xrd_out = self.collected_xrd.take(zero);
+                    xrd_out = self.collected_xrd.take(xrd_fee_out);
             }else{  let delta_candy = candy_out_nbr-total_candy;
                     candy_bucket = match self.candy_vaults.get_mut(&candy_addr) {
                         Some(vault) => vault.take(total_candy),
@@ -476,7 +559,7 @@ blueprint! {
                         }
                     };
                     let price_in: Decimal = CandyDex::candyprice(self, candy_addr);
-                    let xrd_amnt = delta_candy*price_in;
+                    let xrd_amnt = delta_candy*price_in+xrd_fee_out;
                     assert!( xrd_amnt <= self.collected_xrd.amount(), " Not enough XRD in Vault ");
                     xrd_out = self.collected_xrd.take(xrd_amnt);
             }
@@ -492,45 +575,78 @@ blueprint! {
         }
 
             // Retrieve liquidity provider position providing a relative metaBadge as reference.
-        pub fn stock_position(&mut self, meta_badge: Proof) {
+        pub fn stock_position(&self, meta_badge: Proof) -> StockPosition {
             let badge_amnt: Decimal = meta_badge.amount();
             assert!( badge_amnt >= Decimal::one(), " Please provide your own metaBadge as reference ");
             
-            match self.badge_map.get(&meta_badge.resource_address()) {
-                Some((a,b,c)) => { 
-                    let (accrued_fee,total_minted,_meta_address) = self.meta_map.get(&c).unwrap();
-                    let delta_fee: Decimal = *accrued_fee-(*a);
-                    let candy_out_nbr = *b+delta_fee*(*b)/(*total_minted);
-                    info!(" accrued_fee {} ",accrued_fee);
-                    info!(" total_minted {} ",total_minted);
-                    info!(" entry_fee {} ",a);
-                    info!(" delta_fee {} ",delta_fee);
-                    info!(" candy_out_nbr {} ",candy_out_nbr);
-                },
+            let (entry_fee,meta_amnt,candy_addr) = match self.badge_map.get(&meta_badge.resource_address()) {
+                Some(entry) => *entry,
                 None => {
                     info!(" No badge's correspondence! ");
                     std::process::abort()
                 }
+            };
+
+            let (accrued_fee,total_minted,meta_address) = *self.meta_map.get(&candy_addr).unwrap();
+            let delta_fee: Decimal = accrued_fee-entry_fee;
+            let candy_out_nbr = meta_amnt+delta_fee*meta_amnt/total_minted;
+
+            let meta_supply = borrow_resource_manager!(meta_address).total_supply();
+            let share = if meta_supply == Decimal::zero() { Decimal::zero() } else { meta_amnt/meta_supply };
+
+            // Mirror "unstock_candy": any candy amount exceeding protocol reserve is repaid in XRD.
+            let total_candy = self.candy_vaults.get(&candy_addr).unwrap().amount();
+            let (claimable_candy,claimable_xrd) = if candy_out_nbr <= total_candy {
+                (candy_out_nbr,Decimal::zero())
+            } else {
+                (total_candy,(candy_out_nbr-total_candy)*self.candy_map.get(&candy_addr).unwrap().2)
+            };
+            let claimable_xrd_fee = CandyDex::xrd_fee_earned(
+                self, meta_badge.resource_address(), candy_addr, meta_amnt, total_minted
+            );
+
+            StockPosition {
+                candy_address: candy_addr,
+                meta_amount: meta_amnt,
+                share,
+                entry_fee,
+                accrued_fee,
+                claimable_fee: candy_out_nbr-meta_amnt,
+                claimable_candy,
+                claimable_xrd: claimable_xrd+claimable_xrd_fee,
+                claimable_xrd_fee
             }
         }
 
             // Get price, name, symbol of a determinated candy giving his resource address.
-        pub fn get_price(&self, candy_addr: ResourceAddress) {
+        pub fn get_price(&self, candy_addr: ResourceAddress) -> CandyPrice {
             assert!( candy_addr != RADIX_TOKEN, " XRD is priceless ");
            
             match self.candy_map.get(&candy_addr) {
-                Some((a,b,c)) => 
-                    info!(" Address: {}, Name: {}, Symbol: {}, Price: @{} $XRD ",candy_addr ,a,b,c),
-                None => info!("Could not find candy in stock !")
+                Some((a,b,c)) => CandyPrice { 
+                    candy_address: candy_addr, 
+                    name: a.to_string(), 
+                    symbol: b.to_string(), 
+                    price: *c 
+                },
+                None => {
+                    info!(" Could not find candy in stock !");
+                    std::process::abort()
+                }
             }
         }
 
-            // Get reserve amount of a determinated candy giving his resource address.
-        pub fn get_reserve(&self, candy_addr: ResourceAddress) {
-            match self.candy_map.get(&candy_addr) {
-                Some((a,_b,_c)) => { 
-                    let total_candy = self.candy_vaults.get(&candy_addr).unwrap().amount();
-                    info!(" {} candy reserve amount is {} ", a, total_candy);
+            // Get reserve amount, accrued fee and metaCandy supply of a determinated candy giving 
+            // his resource address.
+        pub fn get_reserve(&self, candy_addr: ResourceAddress) -> CandyReserve {
+            match self.meta_map.get(&candy_addr) {
+                Some((accrued_fee,_b,meta_address)) => CandyReserve {
+                    candy_address: candy_addr,
+                    reserve: self.candy_vaults.get(&candy_addr).unwrap().amount(),
+                    accrued_fee: *accrued_fee,
+                    accrued_xrd_fee: CandyDex::xrd_fee_level(self, candy_addr),
+                    meta_address: *meta_address,
+                    meta_supply: borrow_resource_manager!(*meta_address).total_supply()
                 },
                 None => {
                     info!(" Could not find candy in stock !");
@@ -539,13 +655,49 @@ blueprint! {
             }
         }
 
-            // Get protocol's candies menu.
-        pub fn menu(&self){
-            for (addr_name,(str_name,str_sym,price)) in self.candy_map.iter() {
-                info!(" At address {} we've got {} ({}) candy @ {} $XRD each ", addr_name, str_name, str_sym, price);
+            // Get protocol fee percentage along with accrued and claimed XRD fee.
+        pub fn get_fee(&self) -> ProtocolFee {
+            ProtocolFee {
+                fee: self.fee,
+                xrd_fee: self.xrd_fee,
+                xrd_claimed: self.xrd_claimed,
+                xrd_reserve: self.collected_xrd.amount()
             }
         }
 
+            // Get time weighted average price in $XRD of a determinated candy over last "window_epochs"
+            // epochs, current one excluded, up to MAX_TWAP_WINDOW. Passing zero as window returns latest 
+            // candy price.
+        pub fn consult(&self, candy_addr: ResourceAddress, window_epochs: u64) -> Decimal {
+            assert!( 
+                window_epochs <= MAX_TWAP_WINDOW, 
+                " Window can't exceed {} epochs ", MAX_TWAP_WINDOW
+            );
+            match self.oracle_map.get(&candy_addr) {
+                Some(oracle) => {
+                    match oracle.twap(Runtime::current_epoch(), window_epochs) {
+                        Some(twap) => twap,
+                        None => {
+                            info!(" Candy price history shorter than requested window! ");
+                            std::process::abort()
+                        }
+                    }
+                },
+                None => {
+                    info!(" Could not find candy in stock !");
+                    std::process::abort()
+                }
+            }
+        }
+
+            // Get protocol's candies menu.
+        pub fn menu(&self) -> Vec<CandyPrice> {
+            self.candy_map
+                .keys()
+                .map(|candy_addr| self.get_price(*candy_addr))
+                .collect()
+        }
+
             // Get candy sell amount. Use with function "buy_exact_xrd_sell_candy" (bexsc)
         pub fn get_candy_sell_amount_bexsc(
             &mut self, 
@@ -636,42 +788,58 @@ blueprint! {
             // Function swap exact XRD for candy.
         pub fn buy_candy_sell_exact_xrd(
             &mut self, 
-            min_in: Decimal, 
+            min_out: Decimal, 
             addr_in: ResourceAddress, 
-            xrd_out: Bucket
+            xrd_out: Bucket,
+            deadline: u64
         ) -> Bucket {
+            CandyDex::check_deadline(deadline);
+
             let xrd_amnt = xrd_out.amount();
             
             self.collected_xrd.put(xrd_out);
             
             let amount_in = CandyDex::candy_sum(self, xrd_amnt, addr_in, addr_in, 2);
-            assert!( amount_in >= min_in, "Not enough candies output amount");
             
-            CandyDex::candytake(self, amount_in, addr_in)
+            let candy_bucket = CandyDex::candytake(self, amount_in, addr_in);
+            CandyDex::check_min_out(candy_bucket.amount(), min_out);
+
+            candy_bucket
         }
 
             // Obtain a minimum candy amount in exchange of an exact candy amount. 
             // Function swap exact candy for candy.
         pub fn buy_candy_sell_exact_candy(
             &mut self, 
-            min_in: Decimal, 
+            min_out: Decimal, 
             addr_in: ResourceAddress, 
-            candy_out: Bucket
+            candy_out: Bucket,
+            deadline: u64
         ) -> Bucket {
+            CandyDex::check_deadline(deadline);
+
             let addr_out = candy_out.resource_address();            
             assert!(addr_in != addr_out," Same candy's address detect! ");
             
             let (_nmbr,amount_in) = 
                 CandyDex::candyput_pri(self, Decimal::zero(), Decimal::one(), addr_in, candy_out);
 
-            assert!( amount_in >= min_in, "Not enough candies output amount");
-            
-            CandyDex::candytake(self, amount_in, addr_in)
+            let candy_bucket = CandyDex::candytake(self, amount_in, addr_in);
+            CandyDex::check_min_out(candy_bucket.amount(), min_out);
+
+            candy_bucket
         }
 
             // Obtain a minimum XRD amount in exchange of an exact candy amount. 
             // Function swap exact candy for XRD.
-        pub fn buy_xrd_sell_exact_candy(&mut self, xrd_min: Decimal, candy_out: Bucket) -> Bucket {
+        pub fn buy_xrd_sell_exact_candy(
+            &mut self, 
+            min_out: Decimal, 
+            candy_out: Bucket,
+            deadline: u64
+        ) -> Bucket {
+            CandyDex::check_deadline(deadline);
+
             let addr: ResourceAddress = candy_out.resource_address();
             
             let price_out: Decimal = CandyDex::candyprice(self, candy_out.resource_address());
@@ -680,18 +848,17 @@ blueprint! {
             let new_price: Decimal = 
                 CandyDex::price_mod(self, candy_out.amount(), addr, price_out, Decimal::zero());
             
-            let (name,symbol,_price) = self.candy_map.get(&addr).unwrap();
-            let name_str = name.to_string();
-            let symbol_str = symbol.to_string();
-            self.candy_map.insert(addr,(name_str,symbol_str,new_price));
+            CandyDex::set_price(self, addr, new_price);
             
             let (nmbr,_amount_in) = 
                 CandyDex::candyput_pri(self, new_price*new_price, new_price, addr, candy_out);
-            assert!( nmbr >= xrd_min , "Not enough xrd output amount");
              
             self.xrd_fee = self.xrd_fee+nmbr*self.fee/100;
 
-            self.collected_xrd.take(*&(nmbr-nmbr*self.fee/100))
+            let xrd_bucket = self.collected_xrd.take(*&(nmbr-nmbr*self.fee/100));
+            CandyDex::check_min_out(xrd_bucket.amount(), min_out);
+
+            xrd_bucket
         }
 
             // Obtain an exact candy amount in exchange of a maximum XRD amount. 
@@ -700,13 +867,18 @@ blueprint! {
             &mut self, 
             nbr_in: Decimal, 
             addr_in: ResourceAddress, 
-            mut xrd_out: Bucket
+            mut xrd_out: Bucket,
+            max_in: Decimal,
+            deadline: u64
         ) -> (Bucket,Bucket) {
+            CandyDex::check_deadline(deadline);
+
             let amnt_in = CandyDex::adjust_fee(self, nbr_in);
             
             let mut xrd_amnt = CandyDex::candy_sum(self, amnt_in, addr_in, addr_in, 0);
             
             xrd_amnt = amnt_in*amnt_in/xrd_amnt;
+            CandyDex::check_max_in(xrd_amnt, max_in);
             assert!( xrd_amnt <=  xrd_out.amount(), " Not enough XRD input");
             
             self.collected_xrd.put(xrd_out.take(xrd_amnt));
@@ -720,18 +892,24 @@ blueprint! {
             &mut self,            
             amnt_in: Decimal, 
             addr_in: ResourceAddress, 
-            candy_out: Bucket
+            candy_out: Bucket,
+            max_in: Decimal,
+            deadline: u64
         ) -> (Bucket,Bucket) {
+            CandyDex::check_deadline(deadline);
+
             let addr_out = candy_out.resource_address();    
 
             assert!(addr_in != addr_out," Same candy's address detect! ");
             
             let amount_in = CandyDex::adjust_fee(self, amnt_in);
+            let offered_amnt = candy_out.amount();
+
+            let candy_change = 
+                CandyDex::candyput_sec( self, amount_in, addr_in, Decimal::zero(), Decimal::one(), candy_out);
+            CandyDex::check_max_in(offered_amnt-candy_change.amount(), max_in);
             
-            (
-                CandyDex::candyput_sec( self, amount_in, addr_in, Decimal::zero(), Decimal::one(), candy_out),
-                CandyDex::candytake(self, amount_in, addr_in)
-            )
+            (candy_change,CandyDex::candytake(self, amount_in, addr_in))
         }
         
             // Obtain an exact XRD amount in exchange of a maximum candy amount. 
@@ -739,8 +917,12 @@ blueprint! {
         pub fn buy_exact_xrd_sell_candy(
             &mut self, 
             xrd_in: Decimal, 
-            candy_out: Bucket
+            candy_out: Bucket,
+            max_in: Decimal,
+            deadline: u64
         ) -> (Bucket,Bucket) {
+            CandyDex::check_deadline(deadline);
+
             let addr = candy_out.resource_address();
             
             let xrd_input = CandyDex::adjust_fee(self, xrd_in);
@@ -752,89 +934,87 @@ blueprint! {
             let new_price = 
                 CandyDex::price_mod(self, xrd_input/price_out, addr, price_out, dec!(0));
             
-            match self.candy_map.get_mut(&addr) {
-                Some((_a,_b,price)) => *price = new_price,
-                None => std::process::abort()                  
-            };
+            CandyDex::set_price(self, addr, new_price);
 
             self.xrd_fee = self.xrd_fee+xrd_input*self.fee/100;
 
+            let offered_amnt = candy_out.amount();
+            let candy_change = CandyDex::candyput_sec(self, xrd_input, addr, dec!(1), new_price, candy_out);
+            CandyDex::check_max_in(offered_amnt-candy_change.amount(), max_in);
+
             (
-                CandyDex::candyput_sec(self, xrd_input, addr, dec!(1), new_price, candy_out),
+                candy_change,
                 self.collected_xrd.take(*&(xrd_input-xrd_input*self.fee/100))
             )
         }
 
-            // Request a flashswap performing a call to an external Component address.
+            // Request a flashswap lending "amnt_in" tokens to an external Component implementing
+            // FlashswapReceiver interface. Loan must be repaid in "bckt_addr" tokens with protocol 
+            // fee in addiction, surplus is returned to caller.
         pub fn flashswap(
             &mut self, 
             amnt_in: Decimal, 
             addr_in: ResourceAddress, 
             bckt_addr: ResourceAddress, 
-            ext_addr: ComponentAddress, 
-            method: String
+            ext_addr: ComponentAddress
         ) -> Bucket {                
-            let amount_in = CandyDex::adjust_fee(self, amnt_in);
-            
-            let token_bucket: Bucket;
-            let token_output: Bucket;
-
             let price_in: Decimal;
             let price_out: Decimal;
-            
-            if  addr_in == bckt_addr {    
-                price_in = Decimal::one();
-                price_out = Decimal::one();
-
-                if addr_in == RADIX_TOKEN {
-                    token_bucket = self.collected_xrd.take(*&(amount_in-amount_in*self.fee/100));
-                    self.xrd_fee = self.xrd_fee+amount_in*self.fee/100;
-                } else { 
-                    token_bucket = CandyDex::candytake(self, amount_in, addr_in); 
-                }
 
-            } else if addr_in == RADIX_TOKEN && addr_in != bckt_addr {
-                token_bucket = self.collected_xrd.take(*&(amount_in-amount_in*self.fee/100));
-                self.xrd_fee = self.xrd_fee+amount_in*self.fee/100;
+            let token_bucket: Bucket = if addr_in == RADIX_TOKEN {
                 price_in = Decimal::one();
-                price_out = CandyDex::candyprice(self, bckt_addr);
-            } else if addr_in != bckt_addr && bckt_addr == RADIX_TOKEN {
-                token_bucket = CandyDex::candytake(self, amount_in, addr_in);
+                assert!( amnt_in <= self.collected_xrd.amount(), "Not enough XRD in Vault");
+                self.collected_xrd.take(amnt_in)
+            } else {
                 price_in = CandyDex::candyprice(self, addr_in);
+                match self.candy_vaults.get_mut(&addr_in) {
+                    Some(vault) => vault.take(amnt_in),
+                    None => std::process::abort()
+                }
+            };
+
+            if addr_in == bckt_addr {
+                price_out = price_in;
+            } else if bckt_addr == RADIX_TOKEN {
                 price_out = Decimal::one();
-            } else if bckt_addr != RADIX_TOKEN {
-                token_bucket = CandyDex::candytake(self, amount_in, addr_in);
-                price_in = CandyDex::candyprice(self, addr_in);
+            } else {
                 price_out = CandyDex::candyprice(self, bckt_addr);
-            } else { 
-                info!(" Check out addresses! "); 
-                std::process::abort(); 
             }
-            
-            let args = vec![scrypto_encode(&token_bucket),scrypto_encode(&bckt_addr)];
-            
-            let token_return = borrow_component!(ext_addr).call::<Bucket>(&method.to_string(), args);
+
+            let receiver = FlashswapReceiverClient::new(ext_addr);
+            let token_return = receiver.on_flashswap(token_bucket, bckt_addr);
+            assert!(token_return.resource_address() == bckt_addr, " Loan repaid in wrong token! ");
+
+            let loan_nmbr = amnt_in*price_in/price_out;
+            let fee_nmbr = loan_nmbr*self.fee/100;
+            let nmbr = loan_nmbr+fee_nmbr;
+            assert!( token_return.amount() >= nmbr, " Sorry mate, ain't nothin' to scrape! ");
+
+            // Flashswap fee stays in protocol reserves and accrues to liquidity providers of repaid 
+            // candy, or of borrowed candy whenever loan is repaid in $XRD. In the latter case fee is 
+            // held in $XRD vault, so it's accrued and claimed in $XRD. Fee on $XRD loans repaid in 
+            // $XRD is retained by protocol owner.
+            if bckt_addr != RADIX_TOKEN {
+                CandyDex::accrue_fee(self, bckt_addr, fee_nmbr);
+            } else if addr_in != RADIX_TOKEN {
+                CandyDex::accrue_xrd_fee(self, addr_in, fee_nmbr);
+            } else {
+                self.xrd_fee = self.xrd_fee+fee_nmbr;
+            }
 
             let amount = token_return.amount();
-            let nmbr = (amnt_in+amnt_in*self.fee/100)*price_in/price_out;
-            
+
             if bckt_addr != RADIX_TOKEN {   
                 let v = self.candy_vaults.get_mut(&bckt_addr).unwrap();
 
                 v.put(token_return);
 
-                token_output = v.take(amount-nmbr);    
+                v.take(amount-nmbr)
             } else { 
                 self.collected_xrd.put(token_return);
-                token_output = self.collected_xrd.take(*&(amount-nmbr)); 
-            }
 
-            if token_output.amount() < dec!(0) {
-               info!(" Sorry mate, ain't nothin' to scrape! ");
-               std::process::abort();
+                self.collected_xrd.take(amount-nmbr)
             }
-
-            token_output
         }
     }
 }
@@ -864,3 +1044,135 @@ impl MetaToken {
         }
     }
 }
+
+// Price, name and symbol of a candy, returned by "get_price" and "menu" functions.
+#[derive(Debug, Clone, TypeId, Encode, Decode, Describe, PartialEq, Eq)]
+pub struct CandyPrice {
+    pub candy_address: ResourceAddress,
+    pub name: String,
+    pub symbol: String,
+    // Candy price in $XRD.
+    pub price: Decimal,
+}
+
+// Protocol reserve of a candy, returned by "get_reserve" function.
+#[derive(Debug, Clone, TypeId, Encode, Decode, Describe, PartialEq, Eq)]
+pub struct CandyReserve {
+    pub candy_address: ResourceAddress,
+    // Candy amount held in protocol vault.
+    pub reserve: Decimal,
+    // Total candy fee accrued to liquidity providers since candy has been stocked.
+    pub accrued_fee: Decimal,
+    // Total $XRD fee accrued to liquidity providers by flashswaps of the candy repaid in $XRD.
+    pub accrued_xrd_fee: Decimal,
+    pub meta_address: ResourceAddress,
+    // Circulating metaCandy amount.
+    pub meta_supply: Decimal,
+}
+
+// Protocol fee status, returned by "get_fee" function.
+#[derive(Debug, Clone, TypeId, Encode, Decode, Describe, PartialEq, Eq)]
+pub struct ProtocolFee {
+    // Protocol fee percentage.
+    pub fee: Decimal,
+    // Accrued XRD fee claimable by protocol owner.
+    pub xrd_fee: Decimal,
+    // XRD fee already claimed by protocol owner.
+    pub xrd_claimed: Decimal,
+    // XRD amount held in protocol vault.
+    pub xrd_reserve: Decimal,
+}
+
+// Liquidity provider position relative to a metaBadge, returned by "stock_position" function.
+#[derive(Debug, Clone, TypeId, Encode, Decode, Describe, PartialEq, Eq)]
+pub struct StockPosition {
+    pub candy_address: ResourceAddress,
+    // metaCandy amount related to metaBadge.
+    pub meta_amount: Decimal,
+    // Position share of circulating metaCandy supply.
+    pub share: Decimal,
+    // Accrued candy fee level at position entry.
+    pub entry_fee: Decimal,
+    // Current accrued candy fee level.
+    pub accrued_fee: Decimal,
+    // Candy fee earned by position.
+    pub claimable_fee: Decimal,
+    // Candy amount returned by "unstock_candy", fee included.
+    pub claimable_candy: Decimal,
+    // XRD amount returned by "unstock_candy" whenever candy reserve falls short, $XRD fee included.
+    pub claimable_xrd: Decimal,
+    // $XRD fee earned by position.
+    pub claimable_xrd_fee: Decimal,
+}
+
+// Longest window in epochs "consult" can average candy price over.
+pub const MAX_TWAP_WINDOW: u64 = 1000;
+
+// Candy price oracle accumulating price over epochs. Each observation records cumulative price at 
+// its epoch start along with candy price at its epoch end, so prices set and reverted within a single 
+// epoch don't affect time weighted average price. Observations are kept in a ring buffer holding 
+// enough epochs to cover MAX_TWAP_WINDOW.
+#[derive(Debug, Clone, TypeId, Encode, Decode, Describe, PartialEq, Eq)]
+pub struct PriceOracle {
+    // Observations with epoch, cumulative price at epoch start and latest price within epoch.
+    observations: Vec<(u64, Decimal, Decimal)>,
+    // Index of latest observation.
+    newest: usize,
+}
+
+impl PriceOracle {
+    // One observation per epoch, so the oldest one kept is at least MAX_TWAP_WINDOW epochs old.
+    const CAPACITY: usize = MAX_TWAP_WINDOW as usize + 1;
+
+    pub fn new(epoch: u64, price: Decimal) -> Self {
+        Self {
+            observations: vec![(epoch, Decimal::zero(), price)],
+            newest: 0,
+        }
+    }
+
+    // Accumulate price held since last update and record new price.
+    pub fn update(&mut self, epoch: u64, price: Decimal) {
+        let (last_epoch, last_cumulative, last_price) = self.observations[self.newest];
+
+        if epoch == last_epoch {
+            self.observations[self.newest].2 = price;
+            return;
+        }
+
+        let observation = (epoch, last_cumulative + last_price * Decimal::from(epoch - last_epoch), price);
+        if self.observations.len() < Self::CAPACITY {
+            self.observations.push(observation);
+            self.newest = self.observations.len() - 1;
+        } else {
+            // Overwrite oldest observation.
+            self.newest = (self.newest + 1) % Self::CAPACITY;
+            self.observations[self.newest] = observation;
+        }
+    }
+
+    // Observations from newest to oldest.
+    fn history(&self) -> impl Iterator<Item = &(u64, Decimal, Decimal)> {
+        let (recent, oldest) = self.observations.split_at(self.newest + 1);
+        recent.iter().rev().chain(oldest.iter().rev())
+    }
+
+    // Cumulative price up to given epoch start, None if epoch precedes oldest observation.
+    pub fn cumulative_price_at(&self, epoch: u64) -> Option<Decimal> {
+        self.history()
+            .find(|(obs_epoch, _cumulative, _price)| *obs_epoch <= epoch)
+            .map(|(obs_epoch, cumulative, price)| *cumulative + *price * Decimal::from(epoch - *obs_epoch))
+    }
+
+    // Time weighted average price over "window" epochs preceding given epoch.
+    pub fn twap(&self, epoch: u64, window: u64) -> Option<Decimal> {
+        if window == 0 {
+            return Some(self.observations[self.newest].2);
+        }
+
+        let start_cumulative = self.cumulative_price_at(epoch.checked_sub(window)?)?;
+        let end_cumulative = self.cumulative_price_at(epoch)?;
+
+        Some((end_cumulative - start_cumulative) / Decimal::from(window))
+    }
+}
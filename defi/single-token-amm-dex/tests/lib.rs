@@ -0,0 +1,300 @@
+use radix_engine::ledger::*;
+use radix_engine::model::Receipt;
+use radix_engine::transaction::*;
+use scrypto::crypto::{EcdsaPrivateKey, EcdsaPublicKey};
+use scrypto::prelude::*;
+
+use candydex::{CandyReserve, PriceOracle, StockPosition, MAX_TWAP_WINDOW};
+
+struct TestEnv<'a> {
+    executor: TransactionExecutor<'a, InMemorySubstateStore>,
+    public_key: EcdsaPublicKey,
+    private_key: EcdsaPrivateKey,
+    account: ComponentAddress,
+    package: PackageAddress,
+    candy: ResourceAddress,
+    candy_dex: ComponentAddress,
+}
+
+impl<'a> TestEnv<'a> {
+    // Publish package, create a GMG candy and a CandyDex with 1% fee.
+    fn new(ledger: &'a mut InMemorySubstateStore) -> Self {
+        let mut executor = TransactionExecutor::new(ledger, false);
+        let (public_key, private_key, account) = executor.new_account();
+        let package = executor.publish_package(compile_package!()).unwrap();
+
+        let mut candy_information: HashMap<String, String> = HashMap::new();
+        candy_information.insert("name".to_string(), "Gumball".to_string());
+        candy_information.insert("symbol".to_string(), "GMG".to_string());
+
+        let transaction = TransactionBuilder::new()
+            .new_token_fixed(candy_information, dec!("100000"))
+            .call_method_with_all_resources(account, "deposit_batch")
+            .build(executor.get_nonce([public_key]))
+            .sign([&private_key]);
+        let receipt = executor.validate_and_execute(&transaction).unwrap();
+        let candy = receipt.new_resource_addresses[0];
+
+        let transaction = TransactionBuilder::new()
+            .call_function(package, "CandyDex", "new", args![dec!("1")])
+            .call_method_with_all_resources(account, "deposit_batch")
+            .build(executor.get_nonce([public_key]))
+            .sign([&private_key]);
+        let receipt = executor.validate_and_execute(&transaction).unwrap();
+        let candy_dex = receipt.new_component_addresses[0];
+
+        Self {
+            executor,
+            public_key,
+            private_key,
+            account,
+            package,
+            candy,
+            candy_dex,
+        }
+    }
+
+    fn execute(&mut self, builder: &mut TransactionBuilder) -> Receipt {
+        let transaction = builder
+            .call_method_with_all_resources(self.account, "deposit_batch")
+            .build(self.executor.get_nonce([self.public_key]))
+            .sign([&self.private_key]);
+        self.executor.validate_and_execute(&transaction).unwrap()
+    }
+
+    // Stock candies returning the resource address of received metaBadge.
+    fn stock_candy(&mut self, amount: Decimal, price: Decimal) -> ResourceAddress {
+        let (candy, candy_dex) = (self.candy, self.candy_dex);
+        let receipt = self.execute(
+            TransactionBuilder::new()
+                .withdraw_from_account_by_amount(amount, candy, self.account)
+                .take_from_worktop(candy, |builder, bucket_id| {
+                    builder.call_method(candy_dex, "stock_candy", args![scrypto::resource::Bucket(bucket_id), price])
+                }),
+        );
+        assert!(receipt.result.is_ok());
+        // metaCandy resource is created first, then metaBadge resource
+        receipt.new_resource_addresses[1]
+    }
+
+    // Instantiate a FlashswapRepayer holding "funds" of "resource" to repay loans in.
+    fn new_repayer(&mut self, funds: Decimal, resource: ResourceAddress) -> ComponentAddress {
+        let package = self.package;
+        let receipt = self.execute(
+            TransactionBuilder::new()
+                .withdraw_from_account_by_amount(funds, resource, self.account)
+                .take_from_worktop(resource, |builder, bucket_id| {
+                    builder.call_function(package, "FlashswapRepayer", "new", args![scrypto::resource::Bucket(bucket_id)])
+                }),
+        );
+        assert!(receipt.result.is_ok());
+        receipt.new_component_addresses[0]
+    }
+
+    // Borrow "amount" candies repaying them in "repay_address" tokens.
+    fn flashswap(&mut self, amount: Decimal, repay_address: ResourceAddress, receiver: ComponentAddress) -> Receipt {
+        let (candy, candy_dex) = (self.candy, self.candy_dex);
+        self.execute(TransactionBuilder::new().call_method(
+            candy_dex,
+            "flashswap",
+            args![amount, candy, repay_address, receiver],
+        ))
+    }
+
+    // Sell an exact XRD amount for candies.
+    fn buy_candy_sell_exact_xrd(&mut self, amount: Decimal, min_out: Decimal, deadline: u64) -> Receipt {
+        let (account, candy, candy_dex) = (self.account, self.candy, self.candy_dex);
+        self.execute(
+            TransactionBuilder::new()
+                .withdraw_from_account_by_amount(amount, RADIX_TOKEN, account)
+                .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+                    builder.call_method(
+                        candy_dex,
+                        "buy_candy_sell_exact_xrd",
+                        args![min_out, candy, scrypto::resource::Bucket(bucket_id), deadline],
+                    )
+                }),
+        )
+    }
+
+    fn get_reserve(&mut self) -> CandyReserve {
+        let (candy, candy_dex) = (self.candy, self.candy_dex);
+        let receipt = self.execute(TransactionBuilder::new().call_method(candy_dex, "get_reserve", args![candy]));
+        assert!(receipt.result.is_ok());
+        scrypto_decode(&receipt.outputs[0].raw[..]).unwrap()
+    }
+
+    fn stock_position(&mut self, meta_badge: ResourceAddress) -> StockPosition {
+        let candy_dex = self.candy_dex;
+        let receipt = self.execute(
+            TransactionBuilder::new()
+                .create_proof_from_account(meta_badge, self.account)
+                .pop_from_auth_zone(|builder, proof_id| {
+                    builder.call_method(candy_dex, "stock_position", args![scrypto::resource::Proof(proof_id)])
+                }),
+        );
+        assert!(receipt.result.is_ok());
+        scrypto_decode(&receipt.outputs[2].raw[..]).unwrap()
+    }
+}
+
+#[test]
+fn test_flashswap_fee_accrues_to_stockers() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    let meta_badge = env.stock_candy(dec!("6000"), dec!("2"));
+    let repayer = env.new_repayer(dec!("100"), env.candy);
+
+    let receipt = env.flashswap(dec!("1000"), env.candy, repayer);
+    assert!(receipt.result.is_ok());
+
+    let reserve = env.get_reserve();
+    assert_eq!(reserve.reserve, dec!("6010"));
+    assert_eq!(reserve.accrued_fee, dec!("10"));
+    assert_eq!(reserve.meta_supply, dec!("6000"));
+
+    let position = env.stock_position(meta_badge);
+    assert_eq!(position.share, dec!("1"));
+    assert_eq!(position.claimable_fee, dec!("10"));
+    assert_eq!(position.claimable_candy, dec!("6010"));
+    assert_eq!(position.claimable_xrd, dec!("0"));
+}
+
+#[test]
+fn test_flashswap_xrd_fee_accrues_to_stockers() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    let meta_badge = env.stock_candy(dec!("6000"), dec!("2"));
+    let repayer = env.new_repayer(dec!("2100"), RADIX_TOKEN);
+
+    // 1000 candies at price 2 are repaid with 2000 XRD plus 1% fee
+    let receipt = env.flashswap(dec!("1000"), RADIX_TOKEN, repayer);
+    assert!(receipt.result.is_ok());
+
+    let reserve = env.get_reserve();
+    assert_eq!(reserve.reserve, dec!("5000"));
+    assert_eq!(reserve.accrued_fee, dec!("0"));
+    assert_eq!(reserve.accrued_xrd_fee, dec!("20"));
+
+    // Borrowed candies missing from reserve are claimable in XRD along with the fee
+    let position = env.stock_position(meta_badge);
+    assert_eq!(position.claimable_fee, dec!("0"));
+    assert_eq!(position.claimable_candy, dec!("5000"));
+    assert_eq!(position.claimable_xrd_fee, dec!("20"));
+    assert_eq!(position.claimable_xrd, dec!("2020"));
+}
+
+#[test]
+fn test_flashswap_fails_without_fee() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    env.stock_candy(dec!("6000"), dec!("2"));
+    let repayer = env.new_repayer(dec!("1"), env.candy);
+
+    let receipt = env.flashswap(dec!("1000"), env.candy, repayer);
+    assert!(receipt.result.is_err());
+
+    let reserve = env.get_reserve();
+    assert_eq!(reserve.reserve, dec!("6000"));
+    assert_eq!(reserve.accrued_fee, dec!("0"));
+}
+
+#[test]
+fn test_swap_fails_below_min_out() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    env.stock_candy(dec!("6000"), dec!("2"));
+
+    // 100 XRD buy about 49.09 candies after price impact and 1% fee
+    for (min_out, succeeds) in [(dec!("50"), false), (dec!("49"), true)] {
+        let receipt = env.buy_candy_sell_exact_xrd(dec!("100"), min_out, 10);
+        assert_eq!(receipt.result.is_ok(), succeeds);
+    }
+}
+
+#[test]
+fn test_swap_fails_after_deadline() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    env.stock_candy(dec!("6000"), dec!("2"));
+    env.executor.substate_store_mut().set_epoch(10);
+
+    let receipt = env.buy_candy_sell_exact_xrd(dec!("100"), dec!("0"), 9);
+    assert!(receipt.result.is_err());
+
+    let reserve = env.get_reserve();
+    assert_eq!(reserve.reserve, dec!("6000"));
+}
+
+#[test]
+fn test_swap_succeeds_at_deadline() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    env.stock_candy(dec!("6000"), dec!("2"));
+    env.executor.substate_store_mut().set_epoch(10);
+
+    let receipt = env.buy_candy_sell_exact_xrd(dec!("100"), dec!("0"), 10);
+    assert!(receipt.result.is_ok());
+}
+
+#[test]
+fn test_swap_fails_above_max_in() {
+    let mut ledger = InMemorySubstateStore::with_bootstrap();
+    let mut env = TestEnv::new(&mut ledger);
+
+    env.stock_candy(dec!("6000"), dec!("2"));
+
+    // 50 candies cost about 101.87 XRD after price impact and 1% fee
+    let (account, candy, candy_dex) = (env.account, env.candy, env.candy_dex);
+    for (max_in, succeeds) in [(dec!("100"), false), (dec!("102"), true)] {
+        let receipt = env.execute(
+            TransactionBuilder::new()
+                .withdraw_from_account_by_amount(dec!("200"), RADIX_TOKEN, account)
+                .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+                    builder.call_method(
+                        candy_dex,
+                        "buy_exact_candy_sell_xrd",
+                        args![dec!("50"), candy, scrypto::resource::Bucket(bucket_id), max_in, 10u64],
+                    )
+                }),
+        );
+        assert_eq!(receipt.result.is_ok(), succeeds);
+    }
+}
+
+#[test]
+fn test_price_oracle_twap() {
+    let mut oracle = PriceOracle::new(0, dec!("10"));
+    oracle.update(2, dec!("20"));
+    // Price reverted within the same epoch doesn't count
+    oracle.update(3, dec!("1000"));
+    oracle.update(3, dec!("30"));
+
+    assert_eq!(oracle.cumulative_price_at(4), Some(dec!("70")));
+    assert_eq!(oracle.twap(4, 0), Some(dec!("30")));
+    assert_eq!(oracle.twap(4, 2), Some(dec!("25")));
+    assert_eq!(oracle.twap(4, 4), Some(dec!("17.5")));
+    assert_eq!(oracle.twap(4, 5), None);
+}
+
+#[test]
+fn test_price_oracle_keeps_max_window() {
+    let mut oracle = PriceOracle::new(0, dec!("1"));
+    let last_epoch = 3 * MAX_TWAP_WINDOW;
+    for epoch in 1..=last_epoch {
+        oracle.update(epoch, Decimal::from(epoch + 1));
+    }
+
+    // Average of prices held over the window, epoch e holding e + 1
+    let epoch = last_epoch + 1;
+    let expected = Decimal::from((2 * epoch - MAX_TWAP_WINDOW + 1) * MAX_TWAP_WINDOW / 2) / Decimal::from(MAX_TWAP_WINDOW);
+    assert_eq!(oracle.twap(epoch, MAX_TWAP_WINDOW), Some(expected));
+    // Observations older than the window have been overwritten
+    assert_eq!(oracle.cumulative_price_at(epoch - MAX_TWAP_WINDOW - 2), None);
+}
@@ -55,7 +55,7 @@ setEnvValue('privkey2', privkey2);
 const pub_package = (await e($`resim publish .`))[0]
 setEnvValue('package', pub_package);
 
-output = await e($`resim call-function  ${pub_package} LiquidityPool new 1000,${tokenXRD} "LPT" "LP_Token"`)
+output = await e($`resim call-function  ${pub_package} LiquidityPool new 1000,${tokenXRD} "LPT" "LP_Token" 10 0`)
 const component = output[0]
 const lp_mint_bage = output[1]
 const lp_token = output[2]
@@ -72,6 +72,9 @@ await e($`resim call-method ${component} add_liquidity 100,${tokenXRD}`)
 await e($`resim set-default-account ${account} ${privkey}`)
 await e($`resim call-method ${component} add_collected_fee 30,${tokenXRD}`)
 
+// fees are streamed into the pool over 10 epochs
+await e($`resim set-current-epoch 10`)
+
 // add same amount fo liquidity from account2
 await e($`resim set-default-account ${account2} ${privkey2}`)
 await e($`resim call-method ${component} add_liquidity 100,${tokenXRD}`)
@@ -79,6 +82,7 @@ await e($`resim call-method ${component} add_liquidity 100,${tokenXRD}`)
 // simulate pool fee collection
 await e($`resim set-default-account ${account} ${privkey}`)
 await e($`resim call-method ${component} add_collected_fee 50,${tokenXRD}`)
+await e($`resim set-current-epoch 20`)
 
 // Removee all liquidity in the pool
 
@@ -1,20 +1,42 @@
 use scrypto::prelude::*;
 
+#[derive(NonFungibleData)]
+pub struct WithdrawalTicket {
+    amount: Decimal,
+    unlock_epoch: u64,
+}
+
 blueprint! {
     struct LiquidityPool {
         pool: Vault,
         lp_mint_badge: Vault,
         lp_resource_address: ResourceAddress,
         lp_per_asset_ratio:Decimal,
+        // Collected fees not released to the pool yet, they flow into it linearly until fee_stream_end
+        streaming_fees: Vault,
+        fee_stream_epochs: u64,
+        fee_stream_end: u64,
+        last_fee_release: u64,
+        // Withdrawn liquidity waiting for its cooldown to end, it doesn't earn fees anymore
+        withdrawal_cooldown: u64,
+        pending_withdrawals: Vault,
+        ticket_resource_address: ResourceAddress,
+        ticket_id_counter: u64,
     }
 
     impl LiquidityPool {
         /// Creates a LiquidityPool component and returns the component address
         /// along with the initial LP tokens.
+        ///
+        /// Collected fees are released to the pool over `fee_stream_epochs` epochs, so adding liquidity right before
+        /// a large fee and removing it right after only earns what was released in between.  With a non zero
+        /// `withdrawal_cooldown`, liquidity is removed by first trading the LP tokens for a withdrawal ticket.
         pub fn new(
             tokens: Bucket,
             lp_symbol: String,
             lp_name: String,
+            fee_stream_epochs: u64,
+            withdrawal_cooldown: u64,
         ) -> (ComponentAddress,Bucket) {
 
             // Check arguments
@@ -43,14 +65,32 @@ blueprint! {
                 .burnable(rule!(require(lp_mint_badge.resource_address())), LOCKED)
                 .initial_supply(tokens.amount());
             let lp_resource_address = lp_tokens.resource_address();
-            
+
+            // Tickets handed out for LP tokens waiting for the withdrawal cooldown
+            let ticket_resource_address = ResourceBuilder::new_non_fungible()
+                .metadata("name", "LP Withdrawal Ticket")
+                .mintable(rule!(require(lp_mint_badge.resource_address())), LOCKED)
+                .burnable(rule!(require(lp_mint_badge.resource_address())), LOCKED)
+                .no_initial_supply();
+
+            let epoch = Runtime::current_epoch();
             let liquidity_pool = Self {
+                streaming_fees: Vault::new(tokens.resource_address()),
+                pending_withdrawals: Vault::new(tokens.resource_address()),
                 pool: Vault::with_bucket(tokens),
 
                 lp_mint_badge: Vault::with_bucket(lp_mint_badge),
                 lp_resource_address,
 
                 lp_per_asset_ratio: Decimal::one(),
+
+                fee_stream_epochs,
+                fee_stream_end: epoch,
+                last_fee_release: epoch,
+
+                withdrawal_cooldown,
+                ticket_resource_address,
+                ticket_id_counter: 0,
             }
             .instantiate()
             .globalize();
@@ -65,6 +105,8 @@ blueprint! {
             tokens: Bucket
         ) -> Bucket {
 
+            self.release_fees();
+
             // Get the resource manager of the lp tokens
             let lp_resource_manager = borrow_resource_manager!(self.lp_resource_address);
 
@@ -82,25 +124,61 @@ blueprint! {
             lp_tokens
         }
 
-        /// Collect fee for liquidity provider. will be added to the pool and LP/Token will be ajusted accordingly
+        /// Collect fee for liquidity provider. Streamed into the pool over fee_stream_epochs, LP/Token will be
+        /// ajusted accordingly as it is released
         pub fn add_collected_fee(&mut self, 
             tokens: Bucket
         )  {
 
-            self.pool.put(tokens);
+            // Release what is due so far, the rest is streamed again along with the new fee
+            self.release_fees();
+            self.streaming_fees.put(tokens);
+            self.fee_stream_end = Runtime::current_epoch() + self.fee_stream_epochs;
 
-            let lp_resource_manager = borrow_resource_manager!(self.lp_resource_address);
-            if lp_resource_manager.total_supply() !=  Decimal::zero() {
-                self.lp_per_asset_ratio = lp_resource_manager.total_supply() / self.pool.amount();
-            } else {
-                self.lp_per_asset_ratio = Decimal::one()
-            }
+            // Without streaming the fee goes straight to the pool
+            self.release_fees();
         }
 
-        /// Removes liquidity from this pool.
+        /// Burns LP tokens and sets their share of the pool aside until the withdrawal cooldown is over.  The
+        /// returned ticket can then be given to remove_liquidity.
+        pub fn request_withdrawal(&mut self, lp_tokens: Bucket) -> Bucket {
+            self.release_fees();
+
+            let withdrawn = self.withdraw_share(lp_tokens);
+            let data = WithdrawalTicket {
+                amount: withdrawn.amount(),
+                unlock_epoch: Runtime::current_epoch() + self.withdrawal_cooldown,
+            };
+            self.pending_withdrawals.put(withdrawn);
+
+            let ticket = self.lp_mint_badge.authorize(|| {
+                borrow_resource_manager!(self.ticket_resource_address)
+                    .mint_non_fungible(&NonFungibleId::from_u64(self.ticket_id_counter), data)
+            });
+            self.ticket_id_counter += 1;
+
+            ticket
+        }
+
+        /// Removes liquidity from this pool.  Takes LP tokens, or a withdrawal ticket when there is a withdrawal
+        /// cooldown.
         pub fn remove_liquidity(&mut self,
              lp_tokens: Bucket
         ) -> Bucket {
+            if lp_tokens.resource_address() == self.ticket_resource_address {
+                return self.redeem_ticket(lp_tokens);
+            }
+            assert!(
+                self.withdrawal_cooldown == 0,
+                "Liquidity is subject to a cooldown, call request_withdrawal first"
+            );
+
+            self.release_fees();
+            self.withdraw_share(lp_tokens)
+        }
+
+        /// Burns LP tokens and takes their share of the pool
+        fn withdraw_share(&mut self, lp_tokens: Bucket) -> Bucket {
             assert!(
                 self.lp_resource_address == lp_tokens.resource_address(),
                 "Wrong token type passed in"
@@ -125,5 +203,47 @@ blueprint! {
             // Return the withdrawn tokens
             withdrawn
         }
+
+        /// Burns a withdrawal ticket whose cooldown is over and returns what was set aside for it
+        fn redeem_ticket(&mut self, ticket: Bucket) -> Bucket {
+            let data: WithdrawalTicket = borrow_resource_manager!(self.ticket_resource_address)
+                .get_non_fungible_data(&ticket.non_fungible_id());
+            assert!(
+                Runtime::current_epoch() >= data.unlock_epoch,
+                "The withdrawal cooldown is not over yet"
+            );
+
+            self.lp_mint_badge.authorize(|| {
+                ticket.burn();
+            });
+
+            self.pending_withdrawals.take(data.amount)
+        }
+
+        /// Moves the part of the streaming fees due since the last release into the pool
+        fn release_fees(&mut self) {
+            let epoch = Runtime::current_epoch();
+            let to_release = if epoch >= self.fee_stream_end {
+                self.streaming_fees.amount()
+            } else if epoch > self.last_fee_release {
+                self.streaming_fees.amount() * Decimal::from(epoch - self.last_fee_release)
+                    / Decimal::from(self.fee_stream_end - self.last_fee_release)
+            } else {
+                Decimal::zero()
+            };
+            self.last_fee_release = epoch;
+
+            if to_release == Decimal::zero() {
+                return;
+            }
+            self.pool.put(self.streaming_fees.take(to_release));
+
+            let lp_resource_manager = borrow_resource_manager!(self.lp_resource_address);
+            if lp_resource_manager.total_supply() !=  Decimal::zero() {
+                self.lp_per_asset_ratio = lp_resource_manager.total_supply() / self.pool.amount();
+            } else {
+                self.lp_per_asset_ratio = Decimal::one()
+            }
+        }
     }
 }
@@ -1,20 +1,47 @@
 use scrypto::prelude::*;
 
+#[derive(NonFungibleData)]
+pub struct WithdrawalTicket {
+    amount: Decimal,
+    unlock_epoch: u64,
+}
+
 /*
  * Liquidity pool with a single token and fees collection mechanism.
  * This component is not meant to be used by itself. 
  * You should use it inside another component.
+ *
+ * Fees are streamed to the liquidity providers over a number of epochs, and removing
+ * liquidity can be subject to a cooldown, so adding liquidity just before a large
+ * fee and removing it right after does not capture that fee.
  */
 blueprint! {
     struct SingleTokenLiquidityPool {
         pool: Vault,
         fees: Vault,
         lp_minter_badge: Vault,
-        lp_token: ResourceAddress
+        lp_token: ResourceAddress,
+        // Fees not released yet, they flow into `fees` linearly until fee_stream_end
+        streaming_fees: Vault,
+        fee_stream_epochs: u64,
+        fee_stream_end: u64,
+        last_fee_release: u64,
+        // Withdrawn liquidity and fees waiting for their cooldown to end, they don't earn fees anymore
+        withdrawal_cooldown: u64,
+        pending_withdrawals: Vault,
+        ticket_resource_address: ResourceAddress,
+        ticket_id_counter: u64
     }
 
     impl SingleTokenLiquidityPool {
-        pub fn new(initial_funds: Bucket, lp_initial_supply: Decimal) -> ComponentAddress {
+        // Pass 0 as fee_stream_epochs to release fees right away, and as withdrawal_cooldown to let
+        // LP tokens be given to remove_liquidity directly
+        pub fn new(
+            initial_funds: Bucket,
+            lp_initial_supply: Decimal,
+            fee_stream_epochs: u64,
+            withdrawal_cooldown: u64
+        ) -> ComponentAddress {
             let funds_resource_def = initial_funds.resource_address();
 
             // Create badge that will be used to mint and burn LP tokens
@@ -30,29 +57,53 @@ blueprint! {
                 .burnable(rule!(require(lp_minter.resource_address())), LOCKED)
                 .no_initial_supply();
 
+            // Tickets handed out for LP tokens waiting for the withdrawal cooldown
+            let ticket_resource_address = ResourceBuilder::new_non_fungible()
+                .metadata("name", "LP Withdrawal Ticket")
+                .mintable(rule!(require(lp_minter.resource_address())), LOCKED)
+                .burnable(rule!(require(lp_minter.resource_address())), LOCKED)
+                .no_initial_supply();
+
             // Mint initial LP tokens
             lp_minter.authorize(|| {
                 borrow_resource_manager!(lp_token).mint(lp_initial_supply);
             });
 
+            let epoch = Runtime::current_epoch();
             Self {
                 pool: Vault::with_bucket(initial_funds),
                 fees: Vault::new(funds_resource_def),
                 lp_minter_badge: Vault::with_bucket(lp_minter),
-                lp_token: lp_token
-
+                lp_token: lp_token,
+                streaming_fees: Vault::new(funds_resource_def),
+                fee_stream_epochs,
+                fee_stream_end: epoch,
+                last_fee_release: epoch,
+                withdrawal_cooldown,
+                pending_withdrawals: Vault::new(funds_resource_def),
+                ticket_resource_address,
+                ticket_id_counter: 0
             }
             .instantiate().globalize()
         }
 
         // Will be called by other components
         pub fn add_fees(&mut self, fees: Bucket) {
-            self.fees.put(fees);
+            // Release what is due so far, the rest is streamed again along with the new fees
+            self.release_fees();
+            self.streaming_fees.put(fees);
+            self.fee_stream_end = Runtime::current_epoch() + self.fee_stream_epochs;
+
+            // Without streaming the fees are released right away
+            self.release_fees();
         }
 
         // Contribute tokens to the pool in exchange of an LP token
         pub fn add_liquidity(&mut self, liquidity: Bucket) -> Bucket {
-            let pool_share = liquidity.amount() / self.pool.amount();
+            self.release_fees();
+
+            // Released fees belong to the current LPs, they are part of what the new LP buys into
+            let pool_share = liquidity.amount() / (self.pool.amount() + self.fees.amount());
             self.pool.put(liquidity);
 
             // Return newly minted lp_tokens
@@ -62,8 +113,44 @@ blueprint! {
             })
         }
 
-        // Give LP token back to get portion of the pool and fees
+        // Burn LP tokens and set their share of the pool and fees aside until the withdrawal cooldown
+        // is over, the returned ticket can then be given to remove_liquidity
+        pub fn request_withdrawal(&mut self, lp_tokens: Bucket) -> Bucket {
+            self.release_fees();
+
+            let withdrawn = self.withdraw_share(lp_tokens);
+            let data = WithdrawalTicket {
+                amount: withdrawn.amount(),
+                unlock_epoch: Runtime::current_epoch() + self.withdrawal_cooldown
+            };
+            self.pending_withdrawals.put(withdrawn);
+
+            let ticket = self.lp_minter_badge.authorize(|| {
+                borrow_resource_manager!(self.ticket_resource_address)
+                    .mint_non_fungible(&NonFungibleId::from_u64(self.ticket_id_counter), data)
+            });
+            self.ticket_id_counter += 1;
+
+            ticket
+        }
+
+        // Give LP token, or a withdrawal ticket when there is a cooldown, back to get portion of
+        // the pool and fees
         pub fn remove_liquidity(&mut self, lp_tokens: Bucket) -> Bucket {
+            if lp_tokens.resource_address() == self.ticket_resource_address {
+                return self.redeem_ticket(lp_tokens);
+            }
+            assert!(
+                self.withdrawal_cooldown == 0,
+                "Liquidity is subject to a cooldown, call request_withdrawal first"
+            );
+
+            self.release_fees();
+            self.withdraw_share(lp_tokens)
+        }
+
+        // Burn LP tokens and take their share of the pool and fees
+        fn withdraw_share(&mut self, lp_tokens: Bucket) -> Bucket {
             assert!(lp_tokens.resource_address() == self.lp_token, "Wrong LP token !");
 
             let share = lp_tokens.amount() / borrow_resource_manager!(self.lp_token).total_supply();
@@ -78,5 +165,39 @@ blueprint! {
             return_bucket.put(self.pool.take(self.pool.amount() * share));
             return_bucket
         }
+
+        // Burn a withdrawal ticket whose cooldown is over and return what was set aside for it
+        fn redeem_ticket(&mut self, ticket: Bucket) -> Bucket {
+            let data: WithdrawalTicket = borrow_resource_manager!(self.ticket_resource_address)
+                .get_non_fungible_data(&ticket.non_fungible_id());
+            assert!(
+                Runtime::current_epoch() >= data.unlock_epoch,
+                "The withdrawal cooldown is not over yet"
+            );
+
+            self.lp_minter_badge.authorize(|| {
+                ticket.burn();
+            });
+
+            self.pending_withdrawals.take(data.amount)
+        }
+
+        // Move the part of the streaming fees due since the last release to the LPs
+        fn release_fees(&mut self) {
+            let epoch = Runtime::current_epoch();
+            let to_release = if epoch >= self.fee_stream_end {
+                self.streaming_fees.amount()
+            } else if epoch > self.last_fee_release {
+                self.streaming_fees.amount() * Decimal::from(epoch - self.last_fee_release)
+                    / Decimal::from(self.fee_stream_end - self.last_fee_release)
+            } else {
+                Decimal::zero()
+            };
+            self.last_fee_release = epoch;
+
+            if to_release > Decimal::zero() {
+                self.fees.put(self.streaming_fees.take(to_release));
+            }
+        }
     }
 }